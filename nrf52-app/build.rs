@@ -216,12 +216,26 @@ fn main() -> Result<(), Box<dyn Error>> {
     let tx_common_inc = crate_dir.join("../threadx/common/inc");
     let tx_port_dir = crate_dir.join("../threadx/ports/cortex_m4/gnu/src");
     let tx_port_inc = crate_dir.join("../threadx/ports/cortex_m4/gnu/inc");
-    cc::Build::new()
+    let tx_epk_dir = crate_dir.join("../threadx/utility/execution_profile_kit");
+    // threadx-sys generates our tx_user.h, so the kernel and the Rust bindings agree
+    let tx_user_inc = PathBuf::from(env::var("DEP_THREADX_INCLUDE")?);
+    let mut threadx_build = cc::Build::new();
+    threadx_build
         .include(&tx_common_inc)
         .include(&tx_port_inc)
+        .include(&tx_user_inc)
+        .define("TX_INCLUDE_USER_DEFINE_FILE", None)
         .files(TX_PORT_FILES.iter().map(|&s| tx_port_dir.join(s)))
-        .files(TX_COMMON_FILES.iter().map(|&s| tx_common_dir.join(s)))
-        .compile("threadx");
+        .files(TX_COMMON_FILES.iter().map(|&s| tx_common_dir.join(s)));
+    if env::var_os("DEP_THREADX_EXECUTION_PROFILE").is_some() {
+        // The default time source is the DWT cycle counter, which is a 32-bit
+        // counter that main() enables
+        threadx_build
+            .include(&tx_epk_dir)
+            .file(tx_epk_dir.join("tx_execution_profile.c"))
+            .define("TX_EXECUTION_MAX_TIME_SOURCE", "0xFFFFFFFFUL");
+    }
+    threadx_build.compile("threadx");
 
     cc::Build::new()
         .file("src/tx_low_level.S")
@@ -10,12 +10,15 @@ use cortex_m_rt::entry;
 use defmt_rtt as _;
 use nrf52840_hal::prelude::OutputPin;
 use panic_probe as _;
-use static_cell::StaticCell;
+use threadx::{
+    context::{IsrContext, ThreadContext},
+    stack::StackUsage,
+    thread::{Priority, Thread, ThreadOptions},
+};
 
 static BUILD_SLUG: Option<&str> = option_env!("BUILD_SLUG");
 
 const DEMO_STACK_SIZE: usize = 8192;
-const DEMO_POOL_SIZE: usize = (DEMO_STACK_SIZE * 2) + 16384;
 
 const SYSTEM_CLOCK: u32 = 64_000_000;
 const SYSTICK_CYCLES: u32 = (SYSTEM_CLOCK / 100) - 1;
@@ -24,127 +27,89 @@ const SYSTICK_CYCLES: u32 = (SYSTEM_CLOCK / 100) - 1;
 extern "C" fn tx_application_define(_first_unused_memory: *mut core::ffi::c_void) {
     defmt::println!("In tx_application_define()...");
 
-    // ThreadX requires a non-const pointer to char for the names, which it
-    // wil hold on to in the object, so it must have static lifetime. So we
-    // cast-away-const on a static string slice to appease the API.
-
-    let byte_pool = {
-        static BYTE_POOL: StaticCell<threadx_sys::TX_BYTE_POOL> = StaticCell::new();
-        static BYTE_POOL_STORAGE: StaticCell<[u8; DEMO_POOL_SIZE]> = StaticCell::new();
-        let byte_pool = BYTE_POOL.uninit();
-        let byte_pool_storage = BYTE_POOL_STORAGE.uninit();
-        unsafe {
-            threadx_sys::_tx_byte_pool_create(
-                byte_pool.as_mut_ptr(),
-                c"byte-pool0".as_ptr() as *mut threadx_sys::CHAR,
-                byte_pool_storage.as_mut_ptr() as *mut _,
-                DEMO_POOL_SIZE as u32,
-            );
-            byte_pool.assume_init_mut()
-        }
-    };
+    threadx::stack::enable_overflow_detection(on_stack_overflow)
+        .expect("Failed to enable stack checking");
 
     let entry = 0x12345678;
-    let thread0 = {
-        let mut stack_pointer = core::ptr::null_mut();
-        unsafe {
-            threadx_sys::_tx_byte_allocate(
-                byte_pool,
-                &mut stack_pointer,
-                DEMO_STACK_SIZE as _,
-                threadx_sys::TX_NO_WAIT,
-            );
-        }
-        defmt::println!("Stack allocated @ {}", stack_pointer);
-        if stack_pointer.is_null() {
-            panic!("No space for stack");
-        }
-
-        static THREAD_STORAGE: StaticCell<threadx_sys::TX_THREAD> = StaticCell::new();
-        let thread = THREAD_STORAGE.uninit();
-        unsafe {
-            let res = threadx_sys::_tx_thread_create(
-                thread.as_mut_ptr(),
-                c"thread0".as_ptr() as *mut threadx_sys::CHAR,
-                Some(my_thread),
-                entry,
-                stack_pointer,
-                DEMO_STACK_SIZE as _,
-                1,
-                1,
-                threadx_sys::TX_NO_TIME_SLICE,
-                threadx_sys::TX_AUTO_START,
-            );
-            if res != threadx_sys::TX_SUCCESS {
-                panic!("Failed to create thread: {}", res);
-            }
-            thread.assume_init_mut()
-        }
-    };
+    let thread0 = threadx::static_thread!(
+        DEMO_STACK_SIZE,
+        c"thread0",
+        my_thread,
+        entry,
+        ThreadOptions::new(Priority::new(1))
+    )
+    .expect("Failed to create thread");
     defmt::println!(
         "Thread spawned (entry={:08x}) @ {}",
         entry,
-        thread0 as *const _
+        thread0.as_ptr() as *const _
     );
 
     let entry = 0xAABBCCDD;
-    let thread1 = {
-        let mut stack_pointer = core::ptr::null_mut();
-        unsafe {
-            threadx_sys::_tx_byte_allocate(
-                byte_pool,
-                &mut stack_pointer,
-                DEMO_STACK_SIZE as _,
-                threadx_sys::TX_NO_WAIT,
-            );
-        }
-        defmt::println!("Stack allocated @ {:08x}", stack_pointer);
-        if stack_pointer.is_null() {
-            panic!("No space for stack");
-        }
-
-        static THREAD_STORAGE: StaticCell<threadx_sys::TX_THREAD> = StaticCell::new();
-        let thread = THREAD_STORAGE.uninit();
-        unsafe {
-            let res = threadx_sys::_tx_thread_create(
-                thread.as_mut_ptr(),
-                c"thread1".as_ptr() as *mut threadx_sys::CHAR,
-                Some(my_thread),
-                entry,
-                stack_pointer,
-                DEMO_STACK_SIZE as _,
-                1,
-                1,
-                threadx_sys::TX_NO_TIME_SLICE,
-                threadx_sys::TX_AUTO_START,
-            );
-            if res != threadx_sys::TX_SUCCESS {
-                panic!("Failed to create thread: {}", res);
-            }
-            thread.assume_init_mut()
-        }
-    };
+    let thread1 = threadx::static_thread!(
+        DEMO_STACK_SIZE,
+        c"thread1",
+        my_thread,
+        entry,
+        ThreadOptions::new(Priority::new(1))
+    )
+    .expect("Failed to create thread");
     defmt::println!(
         "Thread spawned (entry={:08x}) @ {}",
         entry,
-        thread1 as *const _
+        thread1.as_ptr() as *const _
     );
 }
 
 extern "C" fn my_thread(value: u32) {
     defmt::println!("I am my_thread({:08x})", value);
+    let ctx = ThreadContext::get().expect("Not in a thread");
     let mut thread_counter = 0;
     loop {
         thread_counter += 1;
 
-        unsafe {
-            threadx_sys::_tx_thread_sleep(100);
-        }
+        let _ = threadx::thread::sleep(ctx, 100);
 
         defmt::println!("I am my_thread({:08x}), count = {}", value, thread_counter);
+
+        if thread_counter % 10 == 0 {
+            if let Some(thread) = threadx::thread::current() {
+                let usage = thread.stack_usage();
+                defmt::println!(
+                    "my_thread({:08x}) has used {} of {} bytes of stack",
+                    value,
+                    usage.peak,
+                    usage.size
+                );
+                if let (Ok(mine), Ok(all)) = (thread.cpu_time(), threadx::profile::cpu_time()) {
+                    defmt::println!(
+                        "my_thread({:08x}) has used {} cycles. Threads {}, ISRs {}, idle {}",
+                        value,
+                        mine,
+                        all.threads,
+                        all.isrs,
+                        all.idle
+                    );
+                }
+            }
+        }
     }
 }
 
+/// Called from inside ThreadX when a thread has overrun its stack.
+///
+/// The `threadx` crate only logs the overrun itself with its `defmt` feature,
+/// which needs a newer `defmt` than this app uses, so we log it here.
+fn on_stack_overflow(thread: &Thread, usage: StackUsage) -> ! {
+    defmt::println!(
+        "Stack overflow in thread {} ({} byte stack, {} bytes used)",
+        thread.display_name(),
+        usage.size,
+        usage.peak
+    );
+    cortex_m::asm::udf()
+}
+
 #[entry]
 fn main() -> ! {
     defmt::println!(
@@ -202,7 +167,9 @@ unsafe extern "C" fn __tx_SysTickHandler() {
     extern "C" {
         fn _tx_timer_interrupt();
     }
-    // Call into OS function (not in public API)
-    _tx_timer_interrupt();
+    let ctx = unsafe { IsrContext::new_unchecked() };
+    // Call into OS function (not in public API). The Cortex-M port does not
+    // tell the execution profile kit about interrupts, so we do.
+    threadx::profile::isr(ctx, || _tx_timer_interrupt());
     // Can do any extra work here
 }
@@ -3,7 +3,12 @@
 // SPDX-FileCopyrightText: Copyright (c) 2023 Ferrous Systems
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use std::{env, error::Error, fs, path::PathBuf};
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
 
 static TX_PORT_FILES: &[&str] = &[
     "tx_thread_context_restore.S",
@@ -225,6 +230,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     let tx_common_inc = crate_dir.join("../threadx/common/inc");
     let tx_port_dir = crate_dir.join("../threadx/ports/cortex_r5/gnu/src");
     let tx_port_inc = crate_dir.join("../threadx/ports/cortex_r5/gnu/inc");
+    let tx_epk_dir = crate_dir.join("../threadx/utility/execution_profile_kit");
+    let tx_low_power_dir = crate_dir.join("../threadx/utility/low_power");
+    // threadx-sys generates our tx_user.h, so the kernel and the Rust bindings agree
+    let tx_user_inc = PathBuf::from(env::var("DEP_THREADX_INCLUDE")?);
 
     // Check for ThreadX source code
     for file in TX_PORT_FILES.iter().map(|&s| tx_port_dir.join(s)) {
@@ -235,22 +244,150 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
+    let mut port_files: Vec<PathBuf> = TX_PORT_FILES.iter().map(|&s| tx_port_dir.join(s)).collect();
+
     // Build our ThreadX static library
-    cc::Build::new()
+    let mut threadx_build = cc::Build::new();
+    threadx_build
         .include(&tx_common_inc)
         .include(&tx_port_inc)
+        .include(&tx_user_inc)
         .flag("-g")
+        .define("TX_INCLUDE_USER_DEFINE_FILE", None)
         .define("TX_ENABLE_VFP_SUPPORT", "1")
-        .files(TX_PORT_FILES.iter().map(|&s| tx_port_dir.join(s)))
-        .files(TX_COMMON_FILES.iter().map(|&s| tx_common_dir.join(s)))
-        .compile("threadx");
+        .files(TX_COMMON_FILES.iter().map(|&s| tx_common_dir.join(s)));
+    if env::var_os("CARGO_FEATURE_TICKLESS").is_some() {
+        // The low power utility calls our hooks, which are written in Rust,
+        // in `src/tickless.rs`. The whole kernel is built with them, so that
+        // it all agrees on `TX_LOW_POWER`.
+        let hooks_h = out_dir.join("tx_low_power_hooks.h");
+        fs::write(
+            &hooks_h,
+            "#ifndef __ASSEMBLER__\n\
+             void tickless_timer_setup(unsigned long ticks);\n\
+             void tickless_enter(void);\n\
+             void tickless_exit(void);\n\
+             unsigned long tickless_timer_adjust(void);\n\
+             #endif\n\
+             #define TX_LOW_POWER_TIMER_SETUP(_x) tickless_timer_setup(_x)\n\
+             #define TX_LOW_POWER_USER_ENTER tickless_enter()\n\
+             #define TX_LOW_POWER_USER_EXIT tickless_exit()\n\
+             #define TX_LOW_POWER_USER_TIMER_ADJUST tickless_timer_adjust()\n",
+        )?;
+        threadx_build
+            .include(&tx_low_power_dir)
+            .flag("-include")
+            .flag(hooks_h.display().to_string())
+            .define("TX_LOW_POWER", None)
+            .file(tx_low_power_dir.join("tx_low_power.c"));
+        let schedule = port_files
+            .iter_mut()
+            .find(|file| file.ends_with("tx_thread_schedule.S"))
+            .expect("No scheduler in TX_PORT_FILES");
+        *schedule = low_power_scheduler(schedule, &out_dir)?;
+    }
+    threadx_build.files(&port_files);
+    if env::var_os("DEP_THREADX_EXECUTION_PROFILE").is_some() {
+        // Time execution using SP804 Timer1, which we leave free-running. It
+        // counts down, so flip it around.
+        threadx_build
+            .include(&tx_epk_dir)
+            .file(tx_epk_dir.join("tx_execution_profile.c"))
+            .define(
+                "TX_EXECUTION_TIME_SOURCE",
+                "((ULONG) (0xFFFFFFFFUL - *((volatile ULONG *) 0x101E2024)))",
+            )
+            .define("TX_EXECUTION_MAX_TIME_SOURCE", "0xFFFFFFFFUL");
+    }
+    threadx_build.compile("threadx");
 
-    cc::Build::new()
+    let mut startup_build = cc::Build::new();
+    startup_build
         .include(&tx_common_inc)
         .include(&tx_port_inc)
+        .include(&tx_user_inc)
         .flag("-g")
-        .file("src/tx_initialize_low_level.S")
-        .compile("startup");
+        .define("TX_INCLUDE_USER_DEFINE_FILE", None)
+        .file("src/tx_initialize_low_level.S");
+    if env::var_os("CARGO_FEATURE_IRQ_NESTING").is_some() {
+        // Re-enable IRQs whilst the Rust interrupt handler runs
+        startup_build.define("TX_ENABLE_IRQ_NESTING", None);
+    }
+    if env::var_os("DEP_THREADX_FIQ_SUPPORT").is_some() {
+        // Our start-up code doesn't include tx_user.h, so it needs telling
+        // to call the Rust FIQ handler
+        startup_build.define("TX_ENABLE_FIQ_SUPPORT", None);
+    }
+    startup_build.compile("startup");
 
     Ok(())
 }
+
+/// What to run in the scheduler's idle loop: re-check for a thread to run
+/// with interrupts disabled, and sleep if there still isn't one. Every
+/// register is preserved, as we don't know which ones the loop relies on.
+///
+/// The registers are popped before interrupts are enabled again. An
+/// interrupt taken in the idle loop goes back to the top of the scheduler,
+/// not to where it was taken, so anything still pushed would be lost from
+/// the stack each time.
+const LOW_POWER_IDLE: &str = "    PUSH    {r0-r3, r12, lr}
+    CPSID   i
+    LDR     r0, =_tx_thread_execute_ptr
+    LDR     r0, [r0]
+    CMP     r0, #0
+    BNE     2f
+    BL      tx_low_power_enter
+    BL      tx_low_power_exit
+2:
+    POP     {r0-r3, r12, lr}
+    CPSIE   i
+";
+
+/// Get a version of the port's scheduler which calls the low power hooks
+/// when it has nothing to run.
+///
+/// Unlike the Cortex-M ports, the Cortex-R5 port's scheduler just spins until
+/// a thread is ready, even with `TX_LOW_POWER` defined. If the port calls
+/// `tx_low_power_enter` itself, we use it as it is. Otherwise we put a copy
+/// in `out_dir` with [`LOW_POWER_IDLE`] added to the branch back around its
+/// idle loop, and fail the build unless there is exactly one such branch.
+fn low_power_scheduler(schedule: &Path, out_dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let source = fs::read_to_string(schedule)?;
+    if source.contains("tx_low_power_enter") {
+        // This version of the port does it already
+        return Ok(schedule.to_owned());
+    }
+    let mut patched = String::new();
+    let mut found = 0;
+    for line in source.lines() {
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("B"), Some("__tx_thread_schedule_loop")) => {
+                patched.push_str(LOW_POWER_IDLE);
+                found += 1;
+            }
+            (Some("BEQ"), Some("__tx_thread_schedule_loop")) => {
+                patched.push_str("    BNE     1f\n");
+                patched.push_str(LOW_POWER_IDLE);
+                patched.push_str("    B       __tx_thread_schedule_loop\n1:\n");
+                found += 1;
+                continue;
+            }
+            _ => {}
+        }
+        patched.push_str(line);
+        patched.push('\n');
+    }
+    if found != 1 {
+        return Err(format!(
+            "Expected one branch back around the idle loop in {}, found {}",
+            schedule.display(),
+            found
+        )
+        .into());
+    }
+    let output = out_dir.join("tx_thread_schedule.S");
+    fs::write(&output, patched)?;
+    Ok(output)
+}
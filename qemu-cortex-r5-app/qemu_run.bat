@@ -8,8 +8,14 @@ rem This requires you to previously run `cargo install defmt-print`
 set ELF_BINARY=%1
 set MACHINE=-cpu cortex-r5f -machine versatileab
 set QEMU_PATH=%ProgramFiles%\qemu
+rem Set DEFMT_TRANSPORT=uart if the app was built with the `log-uart` feature,
+rem so that UART2 (rather than semihosting) is what reaches defmt-print. UART0
+rem is then no longer on stdio, so its console is put on a telnet socket instead.
+set SERIAL=
+if "%DEFMT_TRANSPORT%"=="uart" set SERIAL=-serial telnet::4320,server=on,wait=off -serial null -serial stdio
+if "%DEFMT_TRANSPORT%"=="uart" echo UART0 console: telnet localhost 4320
 echo ELF_BINARY=%ELF_BINARY%
 echo Running on '%MACHINE%'...
 echo ------------------------------------------------------------------------
-"%QEMU_PATH%\qemu-system-arm" %MACHINE% -semihosting-config enable=on -nographic -kernel %* | defmt-print -e %ELF_BINARY% --log-format="{[{L}]%%bold} {s} {({ff}:{l:1})%%dimmed}"
+"%QEMU_PATH%\qemu-system-arm" %MACHINE% -semihosting-config enable=on -nographic %SERIAL% -kernel %* | defmt-print -e %ELF_BINARY% --log-format="{t} {[{L}]%%bold} {s} {({ff}:{l:1})%%dimmed}"
 echo ------------------------------------------------------------------------
@@ -8,9 +8,18 @@
 ELF_BINARY=$1
 shift
 MACHINE="-cpu cortex-r5f -machine versatileab"
-LOG_FORMAT='{[{L}]%bold} {s} {({ff}:{l:1})%dimmed}'
+LOG_FORMAT='{t} {[{L}]%bold} {s} {({ff}:{l:1})%dimmed}'
+# Set DEFMT_TRANSPORT=uart if the app was built with the `log-uart` feature, so
+# that UART2 (rather than semihosting) is what reaches defmt-print. UART0 is
+# then no longer on stdio, so its console is put on a telnet socket instead.
+if [ "$DEFMT_TRANSPORT" = "uart" ]; then
+    SERIAL="-serial telnet::4320,server=on,wait=off -serial null -serial stdio"
+    echo "UART0 console: telnet localhost 4320"
+else
+    SERIAL=""
+fi
 echo "ELF_BINARY=$ELF_BINARY"
 echo "Running on '$MACHINE'..."
 echo "------------------------------------------------------------------------"
-qemu-system-arm $MACHINE -semihosting-config enable=on,target=native -nographic -kernel $ELF_BINARY $* | defmt-print -e $ELF_BINARY --log-format="$LOG_FORMAT"
+qemu-system-arm $MACHINE -semihosting-config enable=on,target=native -nographic $SERIAL -kernel $ELF_BINARY $* | defmt-print -e $ELF_BINARY --log-format="$LOG_FORMAT"
 echo "------------------------------------------------------------------------"
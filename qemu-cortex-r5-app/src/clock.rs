@@ -0,0 +1,79 @@
+//! A 64-bit monotonic clock, from SP804 Timer1
+//!
+//! Timer1 counts down freely at [`TIMER_CLOCK_HZ`], and wraps around every
+//! 71 minutes or so. We count the wraps to make a 64-bit clock, which is
+//! given to ThreadX as its [`Clock`] and used for the defmt timestamps.
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use threadx::time::{Clock, Instant};
+
+use crate::sp804_timer::{Interrupts, Mode, Timer1, TIMER_CLOCK_HZ};
+
+/// The clock, which runs once [`Timer1Clock::start`] is called
+pub static CLOCK: Timer1Clock = Timer1Clock::new();
+
+defmt::timestamp!("{=u64:us}", Instant::now().map_or(0, |now| now.as_micros()));
+
+/// A [`Clock`] which counts how many times Timer1 has wrapped
+pub struct Timer1Clock {
+    /// How many times Timer1 has wrapped
+    wraps: AtomicU32,
+    /// How far Timer1 had got, last time we looked
+    last: AtomicU32,
+}
+
+impl Timer1Clock {
+    /// Make a stopped clock
+    const fn new() -> Timer1Clock {
+        Timer1Clock {
+            wraps: AtomicU32::new(0),
+            last: AtomicU32::new(0),
+        }
+    }
+
+    /// Start Timer1 counting, and give the clock to ThreadX.
+    ///
+    /// Timer1 interrupts each time it wraps. Enable
+    /// [`TIMER01_INTERRUPT`](crate::sp804_timer::TIMER01_INTERRUPT), and call
+    /// [`Timer1Clock::handle_interrupt`] when Timer1 is pending, so that no
+    /// wrap is missed.
+    pub fn start(&'static self, mut timer: Timer1) -> Result<(), threadx::Error> {
+        timer.init(u32::MAX, Mode::FreeRunning, Interrupts::Enabled);
+        timer.start();
+        threadx::time::set_clock(self)
+    }
+
+    /// Deal with Timer1 wrapping
+    pub fn handle_interrupt(&self) {
+        Timer1::clear_interrupt();
+        // Reading the clock notices the wrap
+        self.now();
+    }
+}
+
+impl Clock for Timer1Clock {
+    fn frequency_hz(&self) -> u32 {
+        TIMER_CLOCK_HZ
+    }
+
+    fn now(&self) -> u64 {
+        let (wraps, counts) = threadx::context::without_interrupts(|| {
+            // Timer1 counts down, so flip it around
+            let counts = u32::MAX - Timer1::value();
+            let mut wraps = self.wraps.load(Ordering::Relaxed);
+            if counts < self.last.load(Ordering::Relaxed) {
+                wraps += 1;
+                self.wraps.store(wraps, Ordering::Relaxed);
+            }
+            self.last.store(counts, Ordering::Relaxed);
+            (wraps, counts)
+        });
+        (u64::from(wraps) << 32) | u64::from(counts)
+    }
+}
+
+// End of file
@@ -0,0 +1,171 @@
+//! Handlers for the undefined instruction, prefetch abort and data abort
+//! exceptions
+//!
+//! The start-up code saves the registers of whatever was running and calls
+//! [`handle_fault`]. That reports the fault over defmt, along with the fault
+//! status and address registers and the ThreadX thread which was running,
+//! and then exits QEMU with a code that says which exception it was.
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::ffi::CStr;
+
+use cortex_ar::register::{Dfar, Dfsr, Ifar, Ifsr};
+use threadx_sys::TX_THREAD;
+
+extern "C" {
+    /// The thread ThreadX is running, or was running when it was interrupted
+    static _tx_thread_current_ptr: *mut TX_THREAD;
+}
+
+/// The CPSR bit which is set in Thumb state
+const CPSR_T: u32 = 1 << 5;
+
+/// The CPSR bits which give the processor mode
+const CPSR_MODE: u32 = 0x1F;
+
+/// The processor mode ThreadX threads run in
+const SYS_MODE: u32 = 0x1F;
+
+/// User mode, which has the same sp and lr as System mode
+const USR_MODE: u32 = 0x10;
+
+/// The registers of whatever was running when the fault happened, as saved
+/// by the start-up code
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// r0 to r12, with FIQ mode's own r8 to r12 if it was FIQ mode that
+    /// faulted
+    pub r: [u32; 13],
+    /// The stack pointer of the mode that faulted
+    pub sp: u32,
+    /// The link register of the mode that faulted. If that was the exception
+    /// mode itself, this is the same as `return_address`, as its own link
+    /// register has been overwritten.
+    pub lr: u32,
+    /// The exception mode link register, which is a little past the
+    /// faulting instruction
+    pub return_address: u32,
+    /// The CPSR of whatever was running
+    pub cpsr: u32,
+    /// Keeps the stack 8-byte aligned
+    _padding: u32,
+}
+
+impl defmt::Format for Frame {
+    fn format(&self, f: defmt::Formatter) {
+        let r = &self.r;
+        defmt::write!(
+            f,
+            "r0={=u32:08x} r1={=u32:08x} r2={=u32:08x} r3={=u32:08x} \
+             r4={=u32:08x} r5={=u32:08x} r6={=u32:08x} r7={=u32:08x} \
+             r8={=u32:08x} r9={=u32:08x} r10={=u32:08x} r11={=u32:08x} \
+             r12={=u32:08x} sp={=u32:08x} lr={=u32:08x} cpsr={=u32:08x}",
+            r[0],
+            r[1],
+            r[2],
+            r[3],
+            r[4],
+            r[5],
+            r[6],
+            r[7],
+            r[8],
+            r[9],
+            r[10],
+            r[11],
+            r[12],
+            self.sp,
+            self.lr,
+            self.cpsr
+        );
+    }
+}
+
+/// Which exception happened
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum Fault {
+    Undefined,
+    PrefetchAbort,
+    DataAbort,
+    /// The reserved vector, which should never be taken
+    Reserved,
+}
+
+impl Fault {
+    /// Convert the number the start-up code passes us
+    const fn from_raw(kind: u32) -> Fault {
+        match kind {
+            0 => Fault::Undefined,
+            1 => Fault::PrefetchAbort,
+            2 => Fault::DataAbort,
+            _ => Fault::Reserved,
+        }
+    }
+
+    /// What QEMU exits with. A panic exits with 1, and a stack overflow with 6.
+    pub const fn exit_code(self) -> i32 {
+        match self {
+            Fault::Undefined => 2,
+            Fault::PrefetchAbort => 3,
+            Fault::DataAbort => 4,
+            Fault::Reserved => 5,
+        }
+    }
+
+    /// Work out the address of the faulting instruction
+    const fn faulting_pc(self, frame: &Frame) -> u32 {
+        let lr = frame.return_address;
+        match self {
+            Fault::Undefined if (frame.cpsr & CPSR_T) != 0 => lr.wrapping_sub(2),
+            Fault::Undefined | Fault::PrefetchAbort => lr.wrapping_sub(4),
+            Fault::DataAbort => lr.wrapping_sub(8),
+            Fault::Reserved => lr,
+        }
+    }
+}
+
+/// Report a fault, and exit QEMU.
+///
+/// Called by the start-up code, in the exception mode, with interrupts
+/// disabled.
+#[no_mangle]
+extern "C" fn handle_fault(frame: &Frame, kind: u32) -> ! {
+    let fault = Fault::from_raw(kind);
+    defmt::error!("{} at 0x{=u32:08x}", fault, fault.faulting_pc(frame));
+    match fault {
+        Fault::DataAbort => {
+            defmt::error!("DFSR: {}, DFAR: 0x{=u32:08x}", Dfsr::read(), Dfar::read().0);
+        }
+        Fault::PrefetchAbort => {
+            defmt::error!("IFSR: {}, IFAR: 0x{=u32:08x}", Ifsr::read(), Ifar::read().0);
+        }
+        Fault::Undefined | Fault::Reserved => {}
+    }
+    let mode = frame.cpsr & CPSR_MODE;
+    if mode != SYS_MODE && mode != USR_MODE {
+        // Not a thread, so the thread below was interrupted (if any)
+        defmt::error!("Faulted in processor mode 0x{=u32:02x}", mode);
+    }
+    let thread = unsafe { core::ptr::addr_of!(_tx_thread_current_ptr).read_volatile() };
+    if thread.is_null() {
+        defmt::error!("No thread was running");
+    } else {
+        // Safety: ThreadX only points at created threads, and a created
+        // thread's name is a `&'static CStr`
+        let name = unsafe { (*thread).tx_thread_name };
+        let name = if name.is_null() {
+            "<unnamed>"
+        } else {
+            unsafe { CStr::from_ptr(name.cast()) }
+                .to_str()
+                .unwrap_or("<invalid>")
+        };
+        defmt::error!("Thread: {=str} @ 0x{=usize:08x}", name, thread as usize);
+    }
+    defmt::error!("{}", frame);
+    semihosting::process::exit(fault.exit_code());
+}
+
+// End of file
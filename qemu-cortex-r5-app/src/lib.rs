@@ -5,11 +5,22 @@
 
 #![no_std]
 
+pub mod clock;
+pub mod fault;
 pub mod pl011_uart;
 pub mod pl190_vic;
 pub mod sp804_timer;
+#[cfg(feature = "tickless")]
+pub mod tickless;
+
+#[cfg(all(feature = "log-semihosting", feature = "log-uart"))]
+compile_error!("Enable only one of the `log-semihosting` and `log-uart` features");
+
+#[cfg(not(any(feature = "log-semihosting", feature = "log-uart")))]
+compile_error!("Enable one of the `log-semihosting` and `log-uart` features");
 
 // Ensure we pick up the defmt-semihosting transport
+#[cfg(feature = "log-semihosting")]
 use defmt_semihosting as _;
 
 // Ensure we pick up the critical-section impl
@@ -57,6 +68,13 @@ _start:
     // Set stack pointer
     ldr sp, =_stack_top
 
+    // Give the fault handlers their own stacks, then go back to SVC mode
+    cps #0x1B
+    ldr sp, =_und_stack_top
+    cps #0x17
+    ldr sp, =_abt_stack_top
+    cps #0x13
+
     // Allow VFP coprocessor access
     mrc p15, 0, r0, c1, c0, 2
     orr r0, r0, #0xF00000
@@ -72,5 +90,12 @@ _start:
     // In case the application returns, loop forever
     b .
 
+.section .bss.fault_stacks, "aw", %nobits
+.align 3
+    .space 4096
+_und_stack_top:
+    .space 4096
+_abt_stack_top:
+
 "#
 );
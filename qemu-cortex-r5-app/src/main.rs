@@ -6,17 +6,83 @@
 #![no_std]
 #![no_main]
 
+use core::{
+    cell::Cell,
+    pin::Pin,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+};
+
 use qemu_cortex_r5_app::{
-    pl011_uart::Uart,
+    clock,
+    pl011_uart::{self, ReadError, StaticBufferedUart, Uart0, Uart1},
     pl190_vic,
-    sp804_timer::{self, Timer0},
+    sp804_timer::{self, Timer0, Timer1},
+};
+use threadx::{
+    byte_pool::StaticBytePool,
+    context::{IsrContext, ThreadContext},
+    panic::PanicReport,
+    queue::{message_words, StaticQueue},
+    stack::StackUsage,
+    thread::{Priority, Thread, ThreadOptions},
+    watchdog::{Action, StaticWatchdog},
+    work::StaticWorkQueue,
+    Wait,
 };
-use static_cell::StaticCell;
+
+#[cfg(all(feature = "fiq", feature = "irq-nesting"))]
+compile_error!("The `fiq` and `irq-nesting` features both want Timer2");
 
 static BUILD_SLUG: Option<&str> = option_env!("BUILD_SLUG");
 
 const DEMO_STACK_SIZE: usize = 16384;
-const DEMO_POOL_SIZE: usize = (DEMO_STACK_SIZE * 2) + 16384;
+const DEMO_POOL_SIZE: usize = 16384;
+const WORKER_STACK_SIZE: usize = 4096;
+
+/// How many Timer0 counts there are in a ThreadX tick (10ms)
+const TICK_COUNTS: u32 = 10_000;
+
+/// Thread-local values, and spawned threads, are allocated from this pool
+static BYTE_POOL: StaticBytePool<DEMO_POOL_SIZE> = StaticBytePool::new();
+
+/// Threads which have panicked, waiting for the supervisor to restart them
+static RESTART_QUEUE: StaticQueue<
+    Pin<&'static Thread>,
+    { 4 * message_words::<Pin<&'static Thread>>() },
+> = StaticQueue::new();
+
+/// UART0, which is connected to QEMU's stdin
+static CONSOLE: StaticBufferedUart<{ pl011_uart::UART0_ADDR }> = StaticBufferedUart::new();
+
+/// Work deferred from interrupt handlers
+static WORK_QUEUE: StaticWorkQueue<{ threadx::work::words(8) }> = StaticWorkQueue::new();
+
+/// How many Timer0 interrupts there are between reports. Tickless idle
+/// makes them much rarer.
+const REPORT_TICKS: u32 = if cfg!(feature = "tickless") { 10 } else { 500 };
+
+/// How many Timer0 interrupts we have had
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+/// How many Timer2 FIQs we have had
+#[cfg(feature = "fiq")]
+static FIQS: AtomicU32 = AtomicU32::new(0);
+
+/// How many Timer2 IRQs we have had, and how many of them were preempted by
+/// Timer0
+#[cfg(feature = "irq-nesting")]
+static SLOW_IRQS: [AtomicU32; 2] = [AtomicU32::new(0), AtomicU32::new(0)];
+
+/// Watches that thread0 and thread1 keep going around their loops
+static WATCHDOG: StaticWatchdog<2> = StaticWatchdog::new();
+
+/// Set when thread1 has panicked, so it only does it once
+static HAS_PANICKED: AtomicBool = AtomicBool::new(false);
+
+threadx::thread_local! {
+    /// How many times each thread has been around its loop
+    static LOOP_COUNT: Cell<u64> = Cell::new(0);
+}
 
 /// Initialise our application.
 ///
@@ -26,129 +92,255 @@ const DEMO_POOL_SIZE: usize = (DEMO_STACK_SIZE * 2) + 16384;
 extern "C" fn tx_application_define(_first_unused_memory: *mut core::ffi::c_void) {
     defmt::info!("In tx_application_define()...");
 
-    // ThreadX requires a non-const pointer to char for the names, which it
-    // wil hold on to in the object, so it must have static lifetime. So we
-    // cast-away-const on a static string slice to appease the API.
-
-    let byte_pool = {
-        static BYTE_POOL: StaticCell<threadx_sys::TX_BYTE_POOL> = StaticCell::new();
-        static BYTE_POOL_STORAGE: StaticCell<[u8; DEMO_POOL_SIZE]> = StaticCell::new();
-        let byte_pool = BYTE_POOL.uninit();
-        let byte_pool_storage = BYTE_POOL_STORAGE.uninit();
-        unsafe {
-            threadx_sys::_tx_byte_pool_create(
-                byte_pool.as_mut_ptr(),
-                c"byte-pool0".as_ptr() as *mut threadx_sys::CHAR,
-                byte_pool_storage.as_mut_ptr() as *mut _,
-                DEMO_POOL_SIZE as u32,
-            );
-            byte_pool.assume_init_mut()
-        }
-    };
+    threadx::stack::enable_overflow_detection(on_stack_overflow)
+        .expect("Failed to enable stack checking");
 
-    let entry = 0x12345678;
-    let thread0 = {
-        let mut stack_pointer = core::ptr::null_mut();
-        unsafe {
-            threadx_sys::_tx_byte_allocate(
-                byte_pool,
-                &mut stack_pointer,
-                DEMO_STACK_SIZE as _,
-                threadx_sys::TX_NO_WAIT,
-            );
-        }
-        defmt::debug!("Stack allocated @ 0x{=usize:08x}", stack_pointer as usize);
-        if stack_pointer.is_null() {
-            panic!("No space for stack");
-        }
+    let byte_pool = BYTE_POOL
+        .create(c"byte-pool0")
+        .expect("Failed to create pool");
+    threadx::tls::init(byte_pool);
 
-        static THREAD_STORAGE: StaticCell<threadx_sys::TX_THREAD> = StaticCell::new();
-        let thread = THREAD_STORAGE.uninit();
-        unsafe {
-            let res = threadx_sys::_tx_thread_create(
-                thread.as_mut_ptr(),
-                c"thread0".as_ptr() as *mut threadx_sys::CHAR,
-                Some(my_thread),
-                entry,
-                stack_pointer,
-                DEMO_STACK_SIZE as _,
-                1,
-                1,
-                threadx_sys::TX_NO_TIME_SLICE,
-                threadx_sys::TX_AUTO_START,
-            );
-            if res != threadx_sys::TX_SUCCESS {
-                panic!("Failed to create thread: {}", res);
-            }
-            thread.assume_init_mut()
-        }
-    };
+    // A panic in a thread restarts that thread, rather than stopping the system
+    RESTART_QUEUE
+        .create(c"restart-queue")
+        .expect("Failed to create queue");
+    // Safety: none of our threads pin anything on their stacks, or lend
+    // their stacks to other threads outside of a scope
+    unsafe { threadx::panic::enable_isolation(on_thread_panic) };
+    threadx::static_thread!(
+        DEMO_STACK_SIZE,
+        c"supervisor",
+        supervisor_thread,
+        0,
+        ThreadOptions::new(Priority::HIGHEST)
+    )
+    .expect("Failed to create thread");
+
+    threadx::static_thread!(
+        DEMO_STACK_SIZE,
+        c"watchdog",
+        watchdog_thread,
+        0,
+        ThreadOptions::new(Priority::HIGHEST)
+    )
+    .expect("Failed to create thread");
+
+    // Reads lines typed at the console
+    let uart0 = unsafe { Uart0::new_uart0() };
+    CONSOLE
+        .create(uart0, &pl011_uart::Config::default())
+        .expect("Failed to create console");
+    threadx::static_thread!(
+        DEMO_STACK_SIZE,
+        c"console",
+        console_thread,
+        0,
+        ThreadOptions::new(Priority::new(3))
+    )
+    .expect("Failed to create thread");
+
+    // Interrupt handlers hand their slow work to this thread
+    WORK_QUEUE
+        .create(c"work-queue")
+        .expect("Failed to create work queue");
+    threadx::static_thread!(
+        DEMO_STACK_SIZE,
+        c"work",
+        work_thread,
+        0,
+        ThreadOptions::new(Priority::new(2))
+    )
+    .expect("Failed to create thread");
+
+    let entry = 0x12345678;
+    let thread0 = threadx::static_thread!(
+        DEMO_STACK_SIZE,
+        c"thread0",
+        my_thread,
+        entry,
+        ThreadOptions::new(Priority::new(1))
+    )
+    .expect("Failed to create thread");
     defmt::debug!(
         "Thread spawned (entry={=u32:08x}) @ 0x{=usize:08x}",
         entry,
-        thread0 as *const _ as usize
+        thread0.as_ptr() as usize
     );
 
     let entry = 0xAABBCCDD;
-    let thread1 = {
-        let mut stack_pointer = core::ptr::null_mut();
-        unsafe {
-            threadx_sys::_tx_byte_allocate(
-                byte_pool,
-                &mut stack_pointer,
-                DEMO_STACK_SIZE as _,
-                threadx_sys::TX_NO_WAIT,
-            );
-        }
-        defmt::debug!("Stack allocated @ 0x{=usize:08x}", stack_pointer as usize);
-        if stack_pointer.is_null() {
-            panic!("No space for stack");
-        }
-
-        static THREAD_STORAGE2: StaticCell<threadx_sys::TX_THREAD> = StaticCell::new();
-        let thread = THREAD_STORAGE2.uninit();
-        unsafe {
-            let res = threadx_sys::_tx_thread_create(
-                thread.as_mut_ptr(),
-                c"thread1".as_ptr() as *mut threadx_sys::CHAR,
-                Some(my_thread),
-                entry,
-                stack_pointer,
-                DEMO_STACK_SIZE as _,
-                1,
-                1,
-                threadx_sys::TX_NO_TIME_SLICE,
-                threadx_sys::TX_AUTO_START,
-            );
-            if res != threadx_sys::TX_SUCCESS {
-                panic!("Failed to create thread: {}", res);
-            }
-            thread.assume_init_mut()
-        }
-    };
+    let thread1 = threadx::static_thread!(
+        DEMO_STACK_SIZE,
+        c"thread1",
+        my_thread,
+        entry,
+        ThreadOptions::new(Priority::new(1))
+    )
+    .expect("Failed to create thread");
     defmt::debug!(
-        "Thread spawned entry={=u32:08x} @ 0x{=usize:08x}",
+        "Thread spawned (entry={=u32:08x}) @ 0x{=usize:08x}",
         entry,
-        thread1 as *const _ as usize
+        thread1.as_ptr() as usize
     );
 }
 
 /// A function we execute in its own thread.
 extern "C" fn my_thread(value: u32) {
     defmt::info!("I am my_thread({=u32:08x})", value);
-    let mut thread_counter: u64 = 0;
+    let ctx = ThreadContext::get().expect("Not in a thread");
+    let me = threadx::thread::current().expect("Not in a thread");
+    let watch = WATCHDOG
+        .register(me, 300, Action::Log)
+        .expect("Failed to register with watchdog");
     loop {
-        thread_counter += 1;
+        watch.check_in();
+        let thread_counter = LOOP_COUNT.with(|count| {
+            count.set(count.get() + 1);
+            count.get()
+        });
 
-        unsafe {
-            threadx_sys::_tx_thread_sleep(100);
-        }
+        let _ = threadx::thread::sleep(ctx, 100);
 
         defmt::info!(
             "I am my_thread({=u32:08x}), count = {=u64}",
             value,
             thread_counter
         );
+
+        if value == 0xAABBCCDD && thread_counter == 5 && !HAS_PANICKED.swap(true, Ordering::Relaxed)
+        {
+            panic!("Deliberate panic in thread1");
+        }
+
+        if value == 0x12345678 && thread_counter % 10 == 0 {
+            sum_in_worker(ctx, thread_counter);
+        }
+
+        if thread_counter % 10 == 0 {
+            if let Some(thread) = threadx::thread::current() {
+                defmt::info!("my_thread({=u32:08x}) {}", value, thread.stack_usage());
+                if let Ok(performance) = thread.performance_info() {
+                    defmt::info!("my_thread({=u32:08x}) {}", value, performance);
+                }
+                if let (Ok(mine), Ok(all)) = (thread.cpu_time(), threadx::profile::cpu_time()) {
+                    defmt::info!(
+                        "my_thread({=u32:08x}) has used {=u64}/1000 of the CPU. {}",
+                        value,
+                        all.per_mille(mine),
+                        all
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Add up to `limit` on a thread of its own, and wait for the answer.
+fn sum_in_worker(ctx: ThreadContext, limit: u64) {
+    let Some(pool) = BYTE_POOL.get() else {
+        return;
+    };
+    let result = threadx::thread::spawn(
+        ctx,
+        pool,
+        c"worker",
+        WORKER_STACK_SIZE,
+        ThreadOptions::new(Priority::new(2)),
+        move || (1..=limit).sum::<u64>(),
+    )
+    .and_then(|handle| handle.join(ctx));
+    match result {
+        Ok(sum) => defmt::info!("Worker says 1 + ... + {=u64} = {=u64}", limit, sum),
+        Err(e) => defmt::error!("Worker failed: {}", e),
+    }
+}
+
+/// Called on a thread which has panicked, just before it is terminated.
+fn on_thread_panic(ctx: ThreadContext, report: &PanicReport) {
+    // A spawned thread is freed when it is joined, so cannot be restarted
+    let Some(thread) = report.thread.to_static() else {
+        return;
+    };
+    if let Some(queue) = RESTART_QUEUE.get() {
+        let _ = queue.try_send(ctx, thread);
+    }
+}
+
+/// Restarts any thread which panics.
+extern "C" fn supervisor_thread(_value: u32) {
+    let ctx = ThreadContext::get().expect("Not in a thread");
+    let queue = RESTART_QUEUE.get().expect("Restart queue not created");
+    loop {
+        let Ok(thread) = queue.receive(ctx, Wait::FOREVER) else {
+            continue;
+        };
+        defmt::warn!("Restarting thread {=str}", thread.display_name());
+        // Safety: the thread was terminated by panic isolation, which we
+        // enabled knowing that it left nothing behind on its stack
+        if let Err(e) = unsafe { thread.reset() }.and_then(|_| thread.resume()) {
+            defmt::error!("Failed to restart thread: {}", e);
+        }
+        let snapshot = threadx::snapshot::Snapshot::<8>::take();
+        for info in &snapshot.threads {
+            defmt::info!(
+                "Thread {=str}: {}, run count {}",
+                info.name,
+                info.state,
+                info.run_count
+            );
+        }
+    }
+}
+
+/// Checks that the other threads are still making progress.
+extern "C" fn watchdog_thread(_value: u32) {
+    let ctx = ThreadContext::get().expect("Not in a thread");
+    // There is no hardware watchdog to feed on this machine
+    WATCHDOG.run(ctx, 100, || {}, |_expiry| {});
+}
+
+/// Logs each line typed at the console.
+///
+/// UART0 output goes to the same place as our defmt logs, so we cannot echo
+/// what is typed without confusing `defmt-print`.
+extern "C" fn console_thread(_value: u32) {
+    let ctx = ThreadContext::get().expect("Not in a thread");
+    let console = CONSOLE.get().expect("Console not created");
+    let mut line = [0u8; 64];
+    let mut len = 0;
+    loop {
+        match console.read(ctx, Wait::FOREVER) {
+            Ok(b'\r' | b'\n') => {
+                defmt::info!("Console: {=[u8]:a}", &line[..len]);
+                len = 0;
+            }
+            Ok(byte) if len < line.len() => {
+                line[len] = byte;
+                len += 1;
+            }
+            Ok(_) => {}
+            Err(ReadError::Line(e)) => defmt::warn!("Console line error: {}", e),
+            Err(ReadError::Queue(e)) => defmt::error!("Console read failed: {}", e),
+        }
+    }
+}
+
+/// Runs work deferred from interrupt handlers.
+extern "C" fn work_thread(_value: u32) {
+    let ctx = ThreadContext::get().expect("Not in a thread");
+    WORK_QUEUE.get().expect("Work queue not created").run(ctx);
+}
+
+/// Deferred from the timer interrupt every so often.
+fn report_ticks(_ctx: ThreadContext, ticks: usize) {
+    defmt::info!("{=usize} timer ticks so far", ticks);
+    #[cfg(feature = "tickless")]
+    defmt::info!(
+        "ThreadX time is {=u32}, tickless idle: {}",
+        unsafe { threadx_sys::_tx_time_get() },
+        qemu_cortex_r5_app::tickless::stats()
+    );
+    if let Some(work) = WORK_QUEUE.get() {
+        defmt::info!("Work queue: {}", work.stats());
     }
 }
 
@@ -157,28 +349,88 @@ extern "C" fn my_thread(value: u32) {
 /// It is called by the start-up code in `lib.rs`.
 #[no_mangle]
 pub extern "C" fn kmain() {
+    // Anything logged before this is buffered until the UART is ready
+    #[cfg(feature = "log-uart")]
+    pl011_uart::defmt_logger::init(
+        unsafe { pl011_uart::Uart2::new_uart2() },
+        &pl011_uart::Config::default(),
+    )
+    .expect("Failed to start log UART");
+
+    // Timer1 runs freely, as the time source for the log timestamps, for
+    // `threadx::time`, and for the execution profile kit
+    clock::CLOCK
+        .start(unsafe { Timer1::new_timer1() })
+        .expect("Failed to start clock");
+
     defmt::info!(
         "Hello, this is version {}!",
         BUILD_SLUG.unwrap_or("unknown")
     );
 
-    // Create a UART
-    let _uart0 = unsafe { Uart::new_uart0() };
+    // UART1 only goes anywhere if QEMU is given a second `-serial` option
+    let mut uart1 = unsafe { Uart1::new_uart1() };
+    uart1
+        .configure(&pl011_uart::Config::default())
+        .expect("Failed to configure UART1");
+    let _ = embedded_io::Write::write_all(&mut uart1, b"Hello from UART1\r\n");
 
     // Create a timer
     let mut timer0 = unsafe { Timer0::new_timer0() };
     timer0.init(
-        10_000,
+        TICK_COUNTS,
         sp804_timer::Mode::AutoReload,
         sp804_timer::Interrupts::Enabled,
     );
 
-    // Now we need to enable the Timer0 interrupt and connect it to IRQ on this core
-    // It's on PIC interrupt 4.
-    let mut vic = unsafe { pl190_vic::Interrupt::new() };
+    // Now we need to connect the Timer0 interrupt to IRQ on this core. It
+    // gets the highest priority vector, so it is serviced first.
+    let mut vic = unsafe { pl190_vic::Vic::new() };
     vic.init();
-    vic.enable_interrupt(4);
+    vic.register(sp804_timer::TIMER01_INTERRUPT, Some(0), on_timer01)
+        .expect("Registering timer handler");
+    // The console thread gets its input from UART0
+    vic.register(pl011_uart::UART0_INTERRUPT, Some(1), on_uart0)
+        .expect("Registering UART0 handler");
+    #[cfg(feature = "log-uart")]
+    vic.register(
+        pl011_uart::defmt_logger::LOG_UART_INTERRUPT,
+        Some(2),
+        on_log_uart,
+    )
+    .expect("Registering log UART handler");
 
+    #[cfg(feature = "fiq")]
+    {
+        // Timer2 interrupts once a second, as an FIQ
+        let mut timer2 = unsafe { sp804_timer::Timer2::new_timer2() };
+        timer2.init(
+            1_000_000,
+            sp804_timer::Mode::AutoReload,
+            sp804_timer::Interrupts::Enabled,
+        );
+        vic.register_fiq(sp804_timer::TIMER23_INTERRUPT, on_timer2)
+            .expect("Registering Timer2 FIQ handler");
+        timer2.start();
+    }
+
+    #[cfg(feature = "irq-nesting")]
+    {
+        // Timer2 interrupts four times a second, at a lower priority than
+        // Timer0, and takes long enough that Timer0 should preempt it
+        let mut timer2 = unsafe { sp804_timer::Timer2::new_timer2() };
+        timer2.init(
+            250_000,
+            sp804_timer::Mode::AutoReload,
+            sp804_timer::Interrupts::Enabled,
+        );
+        vic.register(sp804_timer::TIMER23_INTERRUPT, Some(3), on_slow_timer2)
+            .expect("Registering Timer2 handler");
+        timer2.start();
+    }
+
+    #[cfg(feature = "tickless")]
+    qemu_cortex_r5_app::tickless::init(TICK_COUNTS);
     timer0.start();
 
     unsafe {
@@ -189,26 +441,161 @@ pub extern "C" fn kmain() {
 }
 
 /// Called from the main interrupt handler
+#[cfg(not(feature = "irq-nesting"))]
 #[no_mangle]
 unsafe extern "C" fn handle_interrupt() {
+    let ctx = unsafe { IsrContext::new_unchecked() };
+    pl190_vic::Vic::dispatch(ctx);
+}
+
+/// Called from the main interrupt handler, before IRQs are re-enabled
+#[cfg(feature = "irq-nesting")]
+#[no_mangle]
+extern "C" fn acknowledge_interrupt() -> u32 {
+    pl190_vic::Vic::acknowledge()
+}
+
+/// Called from the main interrupt handler, with IRQs enabled so that a
+/// higher priority interrupt can preempt this one
+#[cfg(feature = "irq-nesting")]
+#[no_mangle]
+unsafe extern "C" fn handle_nested_interrupt(vector: u32) {
+    let ctx = unsafe { IsrContext::new_unchecked() };
+    pl190_vic::Vic::service(ctx, vector);
+}
+
+/// Called from the main interrupt handler, once IRQs are disabled again
+#[cfg(feature = "irq-nesting")]
+#[no_mangle]
+extern "C" fn end_of_interrupt() {
+    pl190_vic::Vic::end_of_interrupt();
+}
+
+/// Handles the Timer2 IRQ, slowly.
+///
+/// Waits for two Timer0 ticks, which can only happen if Timer0 preempts us.
+#[cfg(feature = "irq-nesting")]
+fn on_slow_timer2(ctx: IsrContext) {
+    sp804_timer::Timer2::clear_interrupt();
+    let start = TICKS.load(Ordering::Relaxed);
+    let mut preempted = false;
+    // Two ticks is 20ms, which is far fewer spins than this
+    for _ in 0..10_000_000 {
+        if TICKS.load(Ordering::Relaxed).wrapping_sub(start) >= 2 {
+            preempted = true;
+            break;
+        }
+        core::hint::spin_loop();
+    }
+    let irqs = SLOW_IRQS[0].fetch_add(1, Ordering::Relaxed) + 1;
+    if preempted {
+        SLOW_IRQS[1].fetch_add(1, Ordering::Relaxed);
+    }
+    if irqs % 8 == 0 {
+        if let Some(work) = WORK_QUEUE.get() {
+            let _ = work.submit(ctx, report_nesting, irqs as usize);
+        }
+    }
+}
+
+/// Deferred from the Timer2 IRQ every so often.
+#[cfg(feature = "irq-nesting")]
+fn report_nesting(_ctx: ThreadContext, irqs: usize) {
+    let preempted = SLOW_IRQS[1].load(Ordering::Relaxed);
+    if preempted == irqs as u32 {
+        defmt::info!("Timer0 preempted all {=usize} Timer2 IRQs", irqs);
+    } else {
+        defmt::error!(
+            "Timer0 preempted only {=u32} of {=usize} Timer2 IRQs",
+            preempted,
+            irqs
+        );
+    }
+}
+
+/// Called from the FIQ handler
+#[cfg(feature = "fiq")]
+#[no_mangle]
+unsafe extern "C" fn handle_fiq() {
+    let ctx = unsafe { IsrContext::new_unchecked() };
+    pl190_vic::Vic::dispatch_fiq(ctx);
+}
+
+/// Handles the Timer2 FIQ.
+///
+/// Not every defmt transport masks FIQs whilst it logs, so the count is
+/// reported from the work queue.
+#[cfg(feature = "fiq")]
+fn on_timer2(ctx: IsrContext) {
+    sp804_timer::Timer2::clear_interrupt();
+    let fiqs = FIQS.fetch_add(1, Ordering::Relaxed) + 1;
+    if fiqs % 5 == 0 {
+        if let Some(work) = WORK_QUEUE.get() {
+            let _ = work.submit(ctx, report_fiqs, fiqs as usize);
+        }
+    }
+}
+
+/// Deferred from the Timer2 FIQ every so often.
+#[cfg(feature = "fiq")]
+fn report_fiqs(_ctx: ThreadContext, fiqs: usize) {
+    defmt::info!("{=usize} FIQs so far", fiqs);
+}
+
+/// Handles the interrupt shared by Timer0, which drives the ThreadX tick, and
+/// Timer1, which drives the clock
+fn on_timer01(ctx: IsrContext) {
     extern "C" {
         fn _tx_timer_interrupt();
     }
 
-    if Timer0::is_pending() {
-        unsafe {
-            _tx_timer_interrupt();
+    if Timer1::is_pending() {
+        clock::CLOCK.handle_interrupt();
+    }
+    if !Timer0::is_pending() {
+        return;
+    }
+    unsafe {
+        _tx_timer_interrupt();
+    }
+    Timer0::clear_interrupt();
+    // Anything slow happens later, in a thread
+    let ticks = TICKS.fetch_add(1, Ordering::Relaxed) + 1;
+    if ticks % REPORT_TICKS == 0 {
+        if let Some(work) = WORK_QUEUE.get() {
+            let _ = work.submit(ctx, report_ticks, ticks as usize);
         }
-        Timer0::clear_interrupt();
     }
 }
 
-/// Called when the application raises an unrecoverable `panic!`.
+/// Handles the UART0 interrupt, on behalf of the console thread
+fn on_uart0(ctx: IsrContext) {
+    CONSOLE.handle_interrupt(ctx);
+}
+
+/// Handles the interrupt from the UART we send log frames to
+#[cfg(feature = "log-uart")]
+fn on_log_uart(_ctx: IsrContext) {
+    pl011_uart::defmt_logger::handle_interrupt();
+}
+
+/// Called from inside ThreadX when a thread has overrun its stack.
+///
+/// ThreadX has already logged which thread it was, and we cannot safely carry
+/// on, so exit QEMU.
+fn on_stack_overflow(_thread: &Thread, _usage: StackUsage) -> ! {
+    semihosting::process::exit(6);
+}
+
+/// Called when the application raises a `panic!`.
 ///
-/// Prints the panic to the console and then exits QEMU using a semihosting
+/// A panic in a thread only terminates that thread. Any other panic is
+/// printed to the console, and then we exit QEMU using a semihosting
 /// breakpoint.
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
+    // Only returns if the panic could not be isolated to one thread
+    threadx::panic::isolate(info);
     defmt::info!("PANIC: {:?}", defmt::Debug2Format(info));
     semihosting::process::exit(1);
 }
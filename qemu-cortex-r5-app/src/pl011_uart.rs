@@ -3,32 +3,289 @@
 // SPDX-FileCopyrightText: Copyright (c) 2024 Ferrous Systems
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use core::{
+    pin::Pin,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use threadx::{
+    context::{Context, IsrContext, ThreadContext},
+    queue::{message_words, Queue, StaticQueue},
+    Error, Wait,
+};
+
+#[cfg(feature = "log-uart")]
+pub mod defmt_logger;
+
+/// The clock fed to the UARTs on an Arm Versatile Application Board
+pub const UART_CLOCK_HZ: u32 = 24_000_000;
+
+/// The address of UART0 on an Arm Versatile Application Board
+pub const UART0_ADDR: usize = 0x101f_1000;
+
+/// The address of UART1 on an Arm Versatile Application Board
+pub const UART1_ADDR: usize = 0x101f_2000;
+
+/// The address of UART2 on an Arm Versatile Application Board
+pub const UART2_ADDR: usize = 0x101f_3000;
+
+/// The address of UART3 on an Arm Versatile Application Board
+pub const UART3_ADDR: usize = 0x1000_9000;
+
+/// The interrupt controller input for UART0
+pub const UART0_INTERRUPT: u8 = 12;
+
+/// The interrupt controller input for UART1
+pub const UART1_INTERRUPT: u8 = 13;
+
+/// The interrupt controller input for UART2
+pub const UART2_INTERRUPT: u8 = 14;
+
+/// The input for UART3 on the secondary interrupt controller (not the VIC)
+pub const UART3_SIC_INTERRUPT: u8 = 6;
+
+/// UART0 on an Arm Versatile Application Board.
+pub type Uart0 = Uart<UART0_ADDR>;
+
+/// UART1 on an Arm Versatile Application Board.
+pub type Uart1 = Uart<UART1_ADDR>;
+
+/// UART2 on an Arm Versatile Application Board.
+pub type Uart2 = Uart<UART2_ADDR>;
+
+/// UART3 on an Arm Versatile Application Board.
+pub type Uart3 = Uart<UART3_ADDR>;
+
+/// How many received bytes (or errors) we buffer
+const RX_QUEUE_LEN: usize = 64;
+
+/// How many bytes waiting to be sent we buffer
+const TX_QUEUE_LEN: usize = 64;
+
+/// Supported parity options
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Parity {
+    None = 0,
+    Even = (1 << 1) | (1 << 2),
+    Odd = 1 << 1,
+}
+
+/// Supported stop bit options
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StopBits {
+    One = 0,
+    Two = 1 << 3,
+}
+
+/// Line settings for a UART. We always use 8 data bits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub baud_rate: u32,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+}
+
+impl Config {
+    /// Work out the baud rate divisor, in 1/64ths, rounded to the nearest.
+    ///
+    /// The integer part must be between 1 and 0xFFFF, and if it is 0xFFFF
+    /// the fractional part must be zero.
+    fn divisor(&self) -> Result<u32, ConfigError> {
+        let divisor = (UART_CLOCK_HZ * 8)
+            .checked_div(self.baud_rate)
+            .ok_or(ConfigError::BaudRate)?
+            .div_ceil(2);
+        if (1 << 6..=0xFFFF << 6).contains(&divisor) {
+            Ok(divisor)
+        } else {
+            Err(ConfigError::BaudRate)
+        }
+    }
+}
+
+impl Default for Config {
+    /// 115200 baud, 8N1
+    fn default() -> Config {
+        Config {
+            baud_rate: 115_200,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+        }
+    }
+}
+
+/// Why a [`Config`] cannot be used
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum ConfigError {
+    /// The baud rate cannot be made from [`UART_CLOCK_HZ`]
+    BaudRate,
+}
+
+/// Something went wrong on the line whilst receiving a byte.
+///
+/// The byte received with the error is discarded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum LineError {
+    /// The receive FIFO was full, so at least one byte was lost
+    Overrun,
+    /// The line was held low for longer than a whole character
+    Break,
+    /// The parity bit was wrong
+    Parity,
+    /// The stop bit was missing
+    Framing,
+}
+
+/// Why reading from a [`BufferedUart`] failed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum ReadError {
+    /// The UART reported a line error
+    Line(LineError),
+    /// Waiting on the receive queue failed, e.g. it timed out
+    Queue(Error),
+}
+
+/// Why creating a [`BufferedUart`] failed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum CreateError {
+    /// The line settings cannot be used
+    Config(ConfigError),
+    /// Creating one of the queues failed
+    Queue(Error),
+}
+
 /// A driver for a virtual PL011 Uart
 ///
-/// It skips almost all the important initialisation, but it works on QEMU.
-pub struct Uart<const ADDR: usize>();
+/// It works on QEMU. Use [`Uart::configure`] to set the line up properly.
+///
+/// As well as `core::fmt::Write`, it implements the blocking `embedded_io`
+/// and the `embedded_io_async` traits. The async versions busy-poll the
+/// UART, waking themselves until it is ready.
+pub struct Uart<const ADDR: usize> {
+    /// A line error found whilst reading ahead, to report on the next read
+    pending: Option<LineError>,
+}
 
-impl Uart<0x101f_1000> {
+impl Uart0 {
     /// Create a new UART object for UART0
     ///
     /// # Safety
     ///
     /// Only construct one object per UART at any given time.
     pub unsafe fn new_uart0() -> Self {
-        let mut u = Uart();
-        u.set_control(Self::CONTROL_UARTEN | Self::CONTROL_TXE);
-        u
+        Self::new_enabled()
+    }
+}
+
+impl Uart1 {
+    /// Create a new UART object for UART1
+    ///
+    /// # Safety
+    ///
+    /// Only construct one object per UART at any given time.
+    pub unsafe fn new_uart1() -> Self {
+        Self::new_enabled()
+    }
+}
+
+impl Uart2 {
+    /// Create a new UART object for UART2
+    ///
+    /// # Safety
+    ///
+    /// Only construct one object per UART at any given time.
+    pub unsafe fn new_uart2() -> Self {
+        Self::new_enabled()
+    }
+}
+
+impl Uart3 {
+    /// Create a new UART object for UART3
+    ///
+    /// # Safety
+    ///
+    /// Only construct one object per UART at any given time.
+    pub unsafe fn new_uart3() -> Self {
+        Self::new_enabled()
     }
 }
 
 impl<const ADDR: usize> Uart<ADDR> {
+    const BASE_PTR: *mut u32 = ADDR as *mut u32;
+
+    const DATA_FE: u32 = 1 << 8;
+    const DATA_PE: u32 = 1 << 9;
+    const DATA_BE: u32 = 1 << 10;
+    const DATA_OE: u32 = 1 << 11;
+
+    const FLAG_BUSY: u32 = 1 << 3;
+    const FLAG_RXFE: u32 = 1 << 4;
     const FLAG_TXFF: u32 = 1 << 5;
+
+    const LINE_FEN: u32 = 1 << 4;
+    const LINE_WLEN_8: u32 = 0b11 << 5;
+
     const CONTROL_UARTEN: u32 = 1 << 0;
     const CONTROL_TXE: u32 = 1 << 8;
+    const CONTROL_RXE: u32 = 1 << 9;
+
+    /// Interrupt when either FIFO is half full
+    const FIFO_LEVEL_HALF: u32 = (0b010 << 3) | 0b010;
+
+    const INT_RX: u32 = 1 << 4;
+    const INT_TX: u32 = 1 << 5;
+    const INT_RT: u32 = 1 << 6;
+    const INT_FE: u32 = 1 << 7;
+    const INT_PE: u32 = 1 << 8;
+    const INT_BE: u32 = 1 << 9;
+    const INT_OE: u32 = 1 << 10;
+    const INT_ALL: u32 = 0x7FF;
+    /// Everything to do with receiving
+    const INT_RX_ALL: u32 =
+        Self::INT_RX | Self::INT_RT | Self::INT_FE | Self::INT_PE | Self::INT_BE | Self::INT_OE;
+
+    // These are in 32-bit word offsets (so * 4 to get byte offsets)
 
     const DATA_OFFSET: usize = 0x000 >> 2;
     const FLAG_OFFSET: usize = 0x018 >> 2;
+    const IBRD_OFFSET: usize = 0x024 >> 2;
+    const FBRD_OFFSET: usize = 0x028 >> 2;
+    const LINE_CONTROL_OFFSET: usize = 0x02C >> 2;
     const CONTROL_OFFSET: usize = 0x030 >> 2;
+    const FIFO_LEVEL_OFFSET: usize = 0x034 >> 2;
+    const IMSC_OFFSET: usize = 0x038 >> 2;
+    const MIS_OFFSET: usize = 0x040 >> 2;
+    const ICR_OFFSET: usize = 0x044 >> 2;
+
+    /// Make a UART object, and turn on the UART and its transmitter
+    fn new_enabled() -> Self {
+        let mut u = Uart { pending: None };
+        u.set_control(Self::CONTROL_UARTEN | Self::CONTROL_TXE);
+        u
+    }
+
+    /// Set the baud rate, parity and stop bits, enable the FIFOs, and enable
+    /// both the transmitter and the receiver.
+    ///
+    /// Assumes the UART is clocked at [`UART_CLOCK_HZ`]. If `config` cannot
+    /// be used, the UART is left as it was.
+    pub fn configure(&mut self, config: &Config) -> Result<(), ConfigError> {
+        let divisor = config.divisor()?;
+        // The PL011 must be disabled, and idle, whilst it is reconfigured
+        self.set_control(0);
+        while (self.get_flags() & Self::FLAG_BUSY) != 0 {}
+        Self::write_reg(Self::IBRD_OFFSET, divisor >> 6);
+        Self::write_reg(Self::FBRD_OFFSET, divisor & 0x3F);
+        // Writing LCR_H latches the divisors too
+        Self::write_reg(
+            Self::LINE_CONTROL_OFFSET,
+            Self::LINE_WLEN_8 | Self::LINE_FEN | config.parity as u32 | config.stop_bits as u32,
+        );
+        Self::write_reg(Self::FIFO_LEVEL_OFFSET, Self::FIFO_LEVEL_HALF);
+        Self::write_reg(Self::ICR_OFFSET, Self::INT_ALL);
+        self.set_control(Self::CONTROL_UARTEN | Self::CONTROL_TXE | Self::CONTROL_RXE);
+        Ok(())
+    }
 
     /// Write a byte (blocking if there's no space)
     pub fn write(&mut self, byte: u8) {
@@ -37,28 +294,88 @@ impl<const ADDR: usize> Uart<ADDR> {
         self.write_data(byte);
     }
 
+    /// Read a byte, if one has arrived
+    pub fn read(&mut self) -> Option<Result<u8, LineError>> {
+        match self.pending.take() {
+            Some(e) => Some(Err(e)),
+            None => Self::poll_rx(),
+        }
+    }
+
+    /// Is there a byte (or an error) waiting to be read?
+    fn can_read(&self) -> bool {
+        self.pending.is_some() || (Self::read_reg(Self::FLAG_OFFSET) & Self::FLAG_RXFE) == 0
+    }
+
+    /// Is there space in the TX FIFO?
+    fn can_write(&self) -> bool {
+        (Self::read_reg(Self::FLAG_OFFSET) & Self::FLAG_TXFF) == 0
+    }
+
+    /// Has everything we wrote gone out on the line?
+    fn is_idle(&self) -> bool {
+        (Self::read_reg(Self::FLAG_OFFSET) & Self::FLAG_BUSY) == 0
+    }
+
+    /// Is an (unmasked) interrupt pending?
+    pub fn is_pending() -> bool {
+        Self::read_reg(Self::MIS_OFFSET) != 0
+    }
+
+    /// Take a byte from the RX FIFO, if there is one
+    fn poll_rx() -> Option<Result<u8, LineError>> {
+        if (Self::read_reg(Self::FLAG_OFFSET) & Self::FLAG_RXFE) != 0 {
+            return None;
+        }
+        let data = Self::read_reg(Self::DATA_OFFSET);
+        Some(if (data & Self::DATA_BE) != 0 {
+            Err(LineError::Break)
+        } else if (data & Self::DATA_FE) != 0 {
+            Err(LineError::Framing)
+        } else if (data & Self::DATA_PE) != 0 {
+            Err(LineError::Parity)
+        } else if (data & Self::DATA_OE) != 0 {
+            Err(LineError::Overrun)
+        } else {
+            Ok(data as u8)
+        })
+    }
+
     /// Write to the data register
     fn write_data(&mut self, value: u8) {
-        unsafe {
-            let ptr = (ADDR as *mut u32).add(Self::DATA_OFFSET);
-            ptr.write_volatile(value as u32);
-        }
+        Self::write_reg(Self::DATA_OFFSET, value as u32);
     }
 
     /// Read from the Flag Register
     fn get_flags(&mut self) -> u32 {
-        unsafe {
-            let ptr = (ADDR as *const u32).add(Self::FLAG_OFFSET);
-            ptr.read_volatile()
-        }
+        Self::read_reg(Self::FLAG_OFFSET)
     }
 
     /// Write to the control register
     fn set_control(&mut self, value: u32) {
-        unsafe {
-            let ptr = (ADDR as *mut u32).add(Self::CONTROL_OFFSET);
-            ptr.write_volatile(value);
-        }
+        Self::write_reg(Self::CONTROL_OFFSET, value);
+    }
+
+    /// Turn on some interrupts, leaving the others as they are
+    fn unmask_interrupts(mask: u32) {
+        let value = Self::read_reg(Self::IMSC_OFFSET);
+        Self::write_reg(Self::IMSC_OFFSET, value | mask);
+    }
+
+    /// Turn off some interrupts, leaving the others as they are
+    fn mask_interrupts(mask: u32) {
+        let value = Self::read_reg(Self::IMSC_OFFSET);
+        Self::write_reg(Self::IMSC_OFFSET, value & !mask);
+    }
+
+    /// Read a register
+    fn read_reg(offset: usize) -> u32 {
+        unsafe { Self::BASE_PTR.add(offset).read_volatile() }
+    }
+
+    /// Write a register
+    fn write_reg(offset: usize, value: u32) {
+        unsafe { Self::BASE_PTR.add(offset).write_volatile(value) }
     }
 }
 
@@ -71,4 +388,270 @@ impl<const N: usize> core::fmt::Write for Uart<N> {
     }
 }
 
+impl embedded_io::Error for LineError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            LineError::Overrun => embedded_io::ErrorKind::Other,
+            LineError::Break | LineError::Parity | LineError::Framing => {
+                embedded_io::ErrorKind::InvalidData
+            }
+        }
+    }
+}
+
+impl<const ADDR: usize> embedded_io::ErrorType for Uart<ADDR> {
+    type Error = LineError;
+}
+
+impl<const ADDR: usize> embedded_io::Read for Uart<ADDR> {
+    /// Wait for at least one byte, then take whatever else has already
+    /// arrived, up to the size of `buf`.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, LineError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while !self.can_read() {}
+        let mut count = 0;
+        while count < buf.len() {
+            match Uart::read(self) {
+                Some(Ok(byte)) => {
+                    buf[count] = byte;
+                    count += 1;
+                }
+                // Hand over what we have, and report the error next time
+                Some(Err(e)) if count > 0 => {
+                    self.pending = Some(e);
+                    break;
+                }
+                Some(Err(e)) => return Err(e),
+                None => break,
+            }
+        }
+        Ok(count)
+    }
+}
+
+impl<const ADDR: usize> embedded_io::ReadReady for Uart<ADDR> {
+    fn read_ready(&mut self) -> Result<bool, LineError> {
+        Ok(self.can_read())
+    }
+}
+
+impl<const ADDR: usize> embedded_io::Write for Uart<ADDR> {
+    /// Wait for space in the TX FIFO, then fill as much of it as we can from
+    /// `buf`.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, LineError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while !self.can_write() {}
+        let mut count = 0;
+        for &byte in buf {
+            if !self.can_write() {
+                break;
+            }
+            self.write_data(byte);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Wait until everything has been sent
+    fn flush(&mut self) -> Result<(), LineError> {
+        while !self.is_idle() {}
+        Ok(())
+    }
+}
+
+impl<const ADDR: usize> embedded_io::WriteReady for Uart<ADDR> {
+    fn write_ready(&mut self) -> Result<bool, LineError> {
+        Ok(self.can_write())
+    }
+}
+
+impl<const ADDR: usize> embedded_io_async::Read for Uart<ADDR> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, LineError> {
+        if !buf.is_empty() {
+            poll_until(|| self.can_read()).await;
+        }
+        embedded_io::Read::read(self, buf)
+    }
+}
+
+impl<const ADDR: usize> embedded_io_async::Write for Uart<ADDR> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, LineError> {
+        if !buf.is_empty() {
+            poll_until(|| self.can_write()).await;
+        }
+        embedded_io::Write::write(self, buf)
+    }
+
+    async fn flush(&mut self) -> Result<(), LineError> {
+        poll_until(|| self.is_idle()).await;
+        Ok(())
+    }
+}
+
+/// Wait until `ready` returns true, asking to be polled again straight away
+/// each time it does not.
+async fn poll_until(ready: impl Fn() -> bool) {
+    core::future::poll_fn(|cx| {
+        if ready() {
+            core::task::Poll::Ready(())
+        } else {
+            cx.waker().wake_by_ref();
+            core::task::Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Storage for an interrupt-driven UART, suitable for placing in a `static`.
+///
+/// Received bytes, and line errors, are put in one ThreadX queue by the
+/// interrupt handler, so that a thread can block waiting for them. Bytes to
+/// send go in another queue, which the interrupt handler drains into the TX
+/// FIFO.
+pub struct StaticBufferedUart<const ADDR: usize> {
+    rx: StaticQueue<
+        Result<u8, LineError>,
+        { RX_QUEUE_LEN * message_words::<Result<u8, LineError>>() },
+    >,
+    tx: StaticQueue<u8, { TX_QUEUE_LEN * message_words::<u8>() }>,
+    rx_dropped: AtomicU32,
+}
+
+impl<const ADDR: usize> StaticBufferedUart<ADDR> {
+    /// Reserve space for the queues. They end up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticBufferedUart<ADDR> {
+        StaticBufferedUart {
+            rx: StaticQueue::new(),
+            tx: StaticQueue::new(),
+            rx_dropped: AtomicU32::new(0),
+        }
+    }
+
+    /// Take over a UART, configure it, create the queues, and enable the
+    /// receive interrupts.
+    ///
+    /// You must also enable the UART's interrupt in the interrupt controller,
+    /// and call [`handle_interrupt`](Self::handle_interrupt) when it fires.
+    pub fn create(
+        &'static self,
+        mut uart: Uart<ADDR>,
+        config: &Config,
+    ) -> Result<BufferedUart<ADDR>, CreateError> {
+        // Check the settings first, so there is nothing to undo
+        config.divisor().map_err(CreateError::Config)?;
+        let rx = self.rx.create(c"uart-rx").map_err(CreateError::Queue)?;
+        let tx = match self.tx.create(c"uart-tx") {
+            Ok(tx) => tx,
+            Err(e) => {
+                // Safety: `get` needs both queues, so nothing else can have
+                // the receive queue yet, and we drop our handle to it here.
+                let _ = unsafe { self.rx.delete() };
+                return Err(CreateError::Queue(e));
+            }
+        };
+        uart.configure(config).map_err(CreateError::Config)?;
+        Uart::<ADDR>::unmask_interrupts(Uart::<ADDR>::INT_RX_ALL);
+        Ok(BufferedUart {
+            rx,
+            tx,
+            rx_dropped: &self.rx_dropped,
+        })
+    }
+
+    /// Get the UART, if it has been created.
+    pub fn get(&'static self) -> Option<BufferedUart<ADDR>> {
+        Some(BufferedUart {
+            rx: self.rx.get()?,
+            tx: self.tx.get()?,
+            rx_dropped: &self.rx_dropped,
+        })
+    }
+
+    /// Deal with an interrupt from this UART.
+    ///
+    /// Moves everything in the RX FIFO into the receive queue, and refills the
+    /// TX FIFO from the transmit queue.
+    pub fn handle_interrupt(&'static self, ctx: IsrContext) {
+        let Some(uart) = self.get() else {
+            // Nobody is listening
+            Uart::<ADDR>::mask_interrupts(Uart::<ADDR>::INT_ALL);
+            return;
+        };
+        let status = Uart::<ADDR>::read_reg(Uart::<ADDR>::MIS_OFFSET);
+        Uart::<ADDR>::write_reg(Uart::<ADDR>::ICR_OFFSET, status);
+        if (status & Uart::<ADDR>::INT_RX_ALL) != 0 {
+            while let Some(received) = Uart::<ADDR>::poll_rx() {
+                if uart.rx.try_send(ctx, received).is_err() {
+                    self.rx_dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        if (status & Uart::<ADDR>::INT_TX) != 0 {
+            uart.fill_tx(ctx);
+        }
+    }
+}
+
+/// A handle to an interrupt-driven UART
+#[derive(Copy, Clone)]
+pub struct BufferedUart<const ADDR: usize> {
+    rx: Pin<&'static Queue<Result<u8, LineError>>>,
+    tx: Pin<&'static Queue<u8>>,
+    rx_dropped: &'static AtomicU32,
+}
+
+impl<const ADDR: usize> BufferedUart<ADDR> {
+    /// Wait for a byte to arrive, or for a line error.
+    pub fn read(&self, ctx: ThreadContext, wait: Wait) -> Result<u8, ReadError> {
+        self.rx
+            .receive(ctx, wait)
+            .map_err(ReadError::Queue)?
+            .map_err(ReadError::Line)
+    }
+
+    /// Queue some bytes to be sent, waiting whenever the queue is full.
+    pub fn write(&self, ctx: ThreadContext, bytes: &[u8]) -> Result<(), Error> {
+        for &byte in bytes {
+            if self.tx.try_send(ctx, byte).is_err() {
+                self.start_tx(ctx);
+                self.tx.send(ctx, byte, Wait::FOREVER)?;
+            }
+        }
+        self.start_tx(ctx);
+        Ok(())
+    }
+
+    /// How many received bytes were lost because the receive queue was full
+    pub fn rx_dropped(&self) -> u32 {
+        self.rx_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Get the interrupt handler going on the transmit queue
+    fn start_tx(&self, ctx: ThreadContext) {
+        // The interrupt handler also takes bytes from the queue, so keep it
+        // out whilst we do, or the bytes could go out in the wrong order.
+        threadx::context::without_interrupts(|| self.fill_tx(ctx));
+    }
+
+    /// Move bytes from the transmit queue to the TX FIFO until one is empty
+    /// or the other is full.
+    ///
+    /// The TX interrupt is left enabled only if there is more to send.
+    fn fill_tx(&self, ctx: impl Context) {
+        while (Uart::<ADDR>::read_reg(Uart::<ADDR>::FLAG_OFFSET) & Uart::<ADDR>::FLAG_TXFF) == 0 {
+            let Ok(byte) = self.tx.try_receive(ctx) else {
+                Uart::<ADDR>::mask_interrupts(Uart::<ADDR>::INT_TX);
+                return;
+            };
+            Uart::<ADDR>::write_reg(Uart::<ADDR>::DATA_OFFSET, byte as u32);
+        }
+        Uart::<ADDR>::unmask_interrupts(Uart::<ADDR>::INT_TX);
+    }
+}
+
 // End of file
@@ -0,0 +1,261 @@
+//! A defmt global logger which sends its frames out of UART2
+//!
+//! This is used instead of `defmt-semihosting` when the `log-uart` feature is
+//! enabled. Encoded frames go into a ring buffer, which the UART's TX
+//! interrupt drains, so logging only has to wait for the UART if the buffer
+//! fills up. Frames logged before [`init`] is called are kept in the buffer
+//! until then, as far as they fit. A frame which does not fit is dropped
+//! whole, so that the ones after it can still be decoded, and [`init`] logs
+//! how many were dropped.
+//!
+//! Interrupts are disabled whilst a frame is being logged, so that threads
+//! and interrupt handlers can all log without mixing up their frames.
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+};
+
+use threadx::context::InterruptPosture;
+
+use super::{Config, ConfigError, Uart, UART2_ADDR};
+
+/// The UART we log to
+type LogUart = Uart<UART2_ADDR>;
+
+/// The interrupt controller input for the UART we log to
+pub const LOG_UART_INTERRUPT: u8 = super::UART2_INTERRUPT;
+
+/// How many encoded bytes we can hold whilst waiting for the UART
+const BUFFER_LEN: usize = 1024;
+
+/// Bytes waiting to go out of the UART
+struct Ring {
+    buffer: [u8; BUFFER_LEN],
+    /// Where the oldest byte is
+    start: usize,
+    /// How many bytes there are
+    len: usize,
+    /// How many of those bytes were there before the current frame started
+    frame_start: usize,
+    /// Set when the current frame did not fit, and is being dropped
+    dropping: bool,
+}
+
+impl Ring {
+    /// Note where a new frame starts, in case it has to be dropped
+    fn start_frame(&mut self) {
+        self.frame_start = self.len;
+        self.dropping = false;
+    }
+
+    /// Add a byte, making room if we have to by waiting for the UART to take
+    /// the oldest one. If we have no UART yet, the whole of the current frame
+    /// is dropped instead.
+    fn push(&mut self, byte: u8, uart: &mut Option<LogUart>) {
+        if self.dropping {
+            return;
+        }
+        if self.len == BUFFER_LEN {
+            let Some(uart) = uart else {
+                self.len = self.frame_start;
+                self.dropping = true;
+                return;
+            };
+            if let Some(oldest) = self.pop() {
+                uart.write(oldest);
+            }
+        }
+        self.buffer[(self.start + self.len) % BUFFER_LEN] = byte;
+        self.len += 1;
+    }
+
+    /// Take the oldest byte
+    fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.buffer[self.start];
+        self.start = (self.start + 1) % BUFFER_LEN;
+        self.len -= 1;
+        self.frame_start = self.frame_start.saturating_sub(1);
+        Some(byte)
+    }
+
+    /// Move bytes into the TX FIFO until it is full, leaving the TX interrupt
+    /// enabled only if there are more to send.
+    fn kick(&mut self, uart: &mut Option<LogUart>) {
+        let Some(uart) = uart else {
+            return;
+        };
+        while uart.can_write() {
+            let Some(byte) = self.pop() else {
+                LogUart::mask_interrupts(LogUart::INT_TX);
+                return;
+            };
+            uart.write_data(byte);
+        }
+        LogUart::unmask_interrupts(LogUart::INT_TX);
+    }
+
+    /// Send every byte, waiting for the UART as we go
+    fn drain(&mut self, uart: &mut Option<LogUart>) {
+        let Some(uart) = uart else {
+            return;
+        };
+        while let Some(byte) = self.pop() {
+            uart.write(byte);
+        }
+        LogUart::mask_interrupts(LogUart::INT_TX);
+    }
+}
+
+/// Everything the logger needs
+struct State {
+    encoder: defmt::Encoder,
+    ring: Ring,
+    uart: Option<LogUart>,
+    /// The interrupt posture to go back to when the frame is finished
+    posture: Option<InterruptPosture>,
+}
+
+/// Holds the logger state in a static
+struct Shared(UnsafeCell<State>);
+
+// Safety: the state is only accessed with interrupts disabled
+unsafe impl Sync for Shared {}
+
+static STATE: Shared = Shared(UnsafeCell::new(State {
+    encoder: defmt::Encoder::new(),
+    ring: Ring {
+        buffer: [0; BUFFER_LEN],
+        start: 0,
+        len: 0,
+        frame_start: 0,
+        dropping: false,
+    },
+    uart: None,
+    posture: None,
+}));
+
+/// Set whilst a frame is being logged
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// How many frames were dropped because they did not fit in the buffer
+static DROPPED: AtomicU32 = AtomicU32::new(0);
+
+/// Start sending log frames out of UART2.
+///
+/// You must also enable [`LOG_UART_INTERRUPT`] in the interrupt controller,
+/// and call [`handle_interrupt`] when it fires.
+///
+/// If `config` cannot be used, the UART is dropped and log frames carry on
+/// being buffered.
+pub fn init(mut uart: Uart<UART2_ADDR>, config: &Config) -> Result<(), ConfigError> {
+    uart.configure(config)?;
+    with_state(|state| {
+        state.uart = Some(uart);
+        state.ring.kick(&mut state.uart);
+    });
+    let dropped = dropped_frames();
+    if dropped > 0 {
+        defmt::warn!(
+            "{} log frames were dropped before the UART was ready",
+            dropped
+        );
+    }
+    Ok(())
+}
+
+/// How many log frames have been dropped because they did not fit in the
+/// buffer before [`init`] was called
+pub fn dropped_frames() -> u32 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Deal with an interrupt from UART2, by refilling its TX FIFO.
+pub fn handle_interrupt() {
+    LogUart::write_reg(LogUart::ICR_OFFSET, LogUart::INT_TX);
+    with_state(|state| state.ring.kick(&mut state.uart));
+}
+
+/// Run `f` on the state with interrupts disabled
+fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> R {
+    threadx::context::without_interrupts(|| {
+        // Safety: interrupts are disabled, and the logger does not call this
+        // whilst it has the state.
+        f(unsafe { &mut *STATE.0.get() })
+    })
+}
+
+#[defmt::global_logger]
+struct Logger;
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        let posture = threadx::context::disable_interrupts();
+        if TAKEN.swap(true, Ordering::Relaxed) {
+            panic!("defmt logger taken reentrantly");
+        }
+        // Safety: interrupts are disabled, and we have the logger
+        let state = unsafe { &mut *STATE.0.get() };
+        state.posture = Some(posture);
+        let State {
+            encoder,
+            ring,
+            uart,
+            ..
+        } = state;
+        ring.start_frame();
+        encoder.start_frame(|bytes| bytes.iter().for_each(|&b| ring.push(b, uart)));
+    }
+
+    unsafe fn flush() {
+        // Safety: interrupts are disabled, and we have the logger
+        let state = unsafe { &mut *STATE.0.get() };
+        state.ring.drain(&mut state.uart);
+    }
+
+    unsafe fn release() {
+        // Safety: interrupts are disabled, and we have the logger
+        let state = unsafe { &mut *STATE.0.get() };
+        let State {
+            encoder,
+            ring,
+            uart,
+            posture,
+        } = state;
+        encoder.end_frame(|bytes| bytes.iter().for_each(|&b| ring.push(b, uart)));
+        if ring.dropping {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+        ring.kick(uart);
+        let posture = posture.take();
+        TAKEN.store(false, Ordering::Relaxed);
+        if let Some(posture) = posture {
+            // Safety: this is the posture `acquire` saved, and everything
+            // else which disables interrupts whilst logging has restored its
+            // own by now
+            unsafe { threadx::context::restore_interrupts(posture) };
+        }
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        // Safety: interrupts are disabled, and we have the logger
+        let state = unsafe { &mut *STATE.0.get() };
+        let State {
+            encoder,
+            ring,
+            uart,
+            ..
+        } = state;
+        encoder.write(bytes, |bytes| {
+            bytes.iter().for_each(|&b| ring.push(b, uart))
+        });
+    }
+}
+
+// End of file
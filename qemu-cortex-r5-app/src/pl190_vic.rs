@@ -1,22 +1,238 @@
 //! Code for the Arm PL190 Vector Interrupt Controller
+//!
+//! Also drives the Secondary Interrupt Controller (SIC) of the Arm Versatile
+//! Application Board, which is chained into input 31 of the VIC.
+//!
+//! Interrupt numbers 0 to 31 are the VIC's inputs. Numbers from
+//! [`SIC_OFFSET`] upwards are the SIC's inputs, so SIC input 6 is
+//! `SIC_OFFSET + 6`.
+//!
+//! VIC inputs can also be routed to FIQ rather than IRQ, with
+//! [`Vic::register_fiq`].
 
 // SPDX-FileCopyrightText: Copyright (c) 2024 Ferrous Systems
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use core::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+
+use threadx::context::IsrContext;
+
+/// Interrupt numbers from here upwards are SIC inputs
+pub const SIC_OFFSET: u8 = 32;
+
+/// The VIC input the SIC is chained into
+const SIC_VIC_INPUT: u8 = 31;
+
+/// How many interrupt numbers there are, across the VIC and the SIC
+const NUM_INTERRUPTS: usize = 64;
+
+/// A function which handles an interrupt
+pub type Handler = fn(IsrContext);
+
+/// The handler for each interrupt number, or null if there isn't one
+static HANDLERS: [AtomicPtr<()>; NUM_INTERRUPTS] =
+    [const { AtomicPtr::new(core::ptr::null_mut()) }; NUM_INTERRUPTS];
+
+/// Which VIC inputs have a vector slot, and so are not found by scanning
+static VECTORED: AtomicU32 = AtomicU32::new(0);
+
+/// Something is wrong with a handler registration
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum Error {
+    /// There is no such interrupt number
+    BadInterrupt(u8),
+    /// There is no such vector slot, or it is already in use
+    BadPriority(u8),
+    /// The interrupt already has a handler
+    AlreadyRegistered(u8),
+}
+
 /// A driver for a virtual PL190 Vector Interrupt Controller
 ///
 /// It might skip some important initialisation, but it works on QEMU.
 pub struct Interrupt<const ADDR: usize>();
 
-impl Interrupt<0x10140000> {
+/// The VIC on an Arm Versatile Application Board
+pub type Vic = Interrupt<0x1014_0000>;
+
+impl Vic {
     /// Create an interrupt controller driver
     ///
     /// # Safety
     ///
     /// Only construct one object per Interrupt Controller at any given time.
-    pub unsafe fn new() -> Interrupt<0x10140000> {
+    pub unsafe fn new() -> Vic {
         Interrupt()
     }
+
+    /// Bind a handler to an interrupt, and enable that interrupt.
+    ///
+    /// A VIC input may be given a `priority`, from 0 (the highest) to 15,
+    /// which is the vector slot it uses. Each slot can only be used once.
+    /// When several interrupts are active, the one in the highest priority
+    /// slot is handled first, and then any without a slot are handled in
+    /// order of their interrupt number. SIC inputs cannot have a slot.
+    pub fn register(
+        &mut self,
+        interrupt: u8,
+        priority: Option<u8>,
+        handler: Handler,
+    ) -> Result<(), Error> {
+        // The SIC's own input is handled by `dispatch`
+        if usize::from(interrupt) >= NUM_INTERRUPTS || interrupt == SIC_VIC_INPUT {
+            return Err(Error::BadInterrupt(interrupt));
+        }
+        if let Some(priority) = priority {
+            if interrupt >= SIC_OFFSET
+                || priority >= Self::NUM_PRIOS
+                || (Self::read_reg(Self::vect_ctrl_offset(priority)) & Self::CNTL_ENABLE) != 0
+            {
+                return Err(Error::BadPriority(priority));
+            }
+        }
+        HANDLERS[usize::from(interrupt)]
+            .compare_exchange(
+                core::ptr::null_mut(),
+                handler as *mut (),
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            )
+            .map_err(|_| Error::AlreadyRegistered(interrupt))?;
+        if let Some(priority) = priority {
+            // The vector address is what reading VICVectAddr gives us back.
+            // Zero is reserved for "not vectored".
+            Self::write_reg(Self::vect_addr_offset(priority), u32::from(interrupt) + 1);
+            Self::write_reg(
+                Self::vect_ctrl_offset(priority),
+                Self::CNTL_ENABLE | u32::from(interrupt),
+            );
+            VECTORED.fetch_or(1 << interrupt, Ordering::Relaxed);
+        }
+        self.enable_interrupt(interrupt);
+        Ok(())
+    }
+
+    /// Bind a handler to an interrupt, route it to FIQ instead of IRQ, and
+    /// enable it.
+    ///
+    /// Only VIC inputs can be routed to FIQ. ThreadX must be built with
+    /// `TX_ENABLE_FIQ_SUPPORT` if the handler calls any ThreadX services.
+    pub fn register_fiq(&mut self, interrupt: u8, handler: Handler) -> Result<(), Error> {
+        if interrupt >= Self::NUM_IRQS || interrupt == SIC_VIC_INPUT {
+            return Err(Error::BadInterrupt(interrupt));
+        }
+        HANDLERS[usize::from(interrupt)]
+            .compare_exchange(
+                core::ptr::null_mut(),
+                handler as *mut (),
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            )
+            .map_err(|_| Error::AlreadyRegistered(interrupt))?;
+        let select = Self::read_reg(Self::INT_SELECT_OFFSET);
+        Self::write_reg(Self::INT_SELECT_OFFSET, select | (1 << interrupt));
+        self.enable_interrupt(interrupt);
+        Ok(())
+    }
+
+    /// Run the handlers for every active interrupt that was routed to FIQ.
+    ///
+    /// Call this from the FIQ handler.
+    pub fn dispatch_fiq(ctx: IsrContext) {
+        let mut status = Self::read_reg(Self::FIQ_STATUS_OFFSET);
+        while status != 0 {
+            let interrupt = status.trailing_zeros() as u8;
+            status &= status - 1;
+            Self::run_handler(ctx, interrupt);
+        }
+    }
+
+    /// Run the handlers for every active interrupt.
+    ///
+    /// Call this from the IRQ handler, if IRQs are not nested.
+    pub fn dispatch(ctx: IsrContext) {
+        let vector = Self::acknowledge();
+        Self::service(ctx, vector);
+        Self::end_of_interrupt();
+    }
+
+    /// Tell the VIC we are handling an interrupt, and get its vector.
+    ///
+    /// Until [`Vic::end_of_interrupt`] is called, the VIC only signals IRQs
+    /// which are in a higher priority vector slot than this one. Interrupts
+    /// without a slot count as lower than every slot. This is what lets IRQs
+    /// be nested safely: the interrupt being handled, and anything of the
+    /// same or lower priority, cannot fire again until we have finished.
+    pub fn acknowledge() -> u32 {
+        Self::read_reg(Self::VECT_ADDR_OFFSET)
+    }
+
+    /// Run the handlers for a vector returned by [`Vic::acknowledge`].
+    ///
+    /// That is either the one interrupt in that vector slot, or every active
+    /// interrupt without a slot. This may be called with IRQs enabled.
+    pub fn service(ctx: IsrContext, vector: u32) {
+        if vector != Self::NO_VECTOR {
+            Self::run_handler(ctx, (vector - 1) as u8);
+            // Interrupts without a slot will fire again once we are done.
+            // Handling them here could run their handlers re-entrantly, if
+            // we have interrupted one of them.
+            return;
+        }
+        let mut status =
+            Self::read_reg(Self::IRQ_STATUS_OFFSET) & !VECTORED.load(Ordering::Relaxed);
+        while status != 0 {
+            let interrupt = status.trailing_zeros() as u8;
+            status &= status - 1;
+            if interrupt == SIC_VIC_INPUT {
+                Self::dispatch_sic(ctx);
+            } else {
+                Self::run_handler(ctx, interrupt);
+            }
+        }
+    }
+
+    /// Tell the VIC we have finished with the most recently acknowledged
+    /// interrupt, so lower priorities can go again.
+    ///
+    /// Call this with IRQs disabled.
+    pub fn end_of_interrupt() {
+        Self::write_reg(Self::VECT_ADDR_OFFSET, 0);
+    }
+
+    /// Run the handlers for every active SIC input
+    fn dispatch_sic(ctx: IsrContext) {
+        let mut status = Sic::read_reg(Sic::STATUS_OFFSET);
+        while status != 0 {
+            let input = status.trailing_zeros() as u8;
+            status &= status - 1;
+            Self::run_handler(ctx, SIC_OFFSET + input);
+        }
+    }
+
+    /// Run the handler for one interrupt.
+    ///
+    /// Clears the interrupt first if it was raised by software. An interrupt
+    /// without a handler is disabled, so it does not fire forever.
+    fn run_handler(ctx: IsrContext, interrupt: u8) {
+        if let Some(input) = interrupt.checked_sub(SIC_OFFSET) {
+            if (Sic::read_reg(Sic::SOFT_INT_OFFSET) & (1 << input)) != 0 {
+                Sic::write_reg(Sic::SOFT_INT_CLEAR_OFFSET, 1 << input);
+            }
+        } else if (Self::read_reg(Self::SOFT_INT_OFFSET) & (1 << interrupt)) != 0 {
+            Self::write_reg(Self::SOFT_INT_CLEAR_OFFSET, 1 << interrupt);
+        }
+        let handler = HANDLERS[usize::from(interrupt)].load(Ordering::Acquire);
+        if handler.is_null() {
+            // Safety: `disable_interrupt` only writes to a write-one-to-clear
+            // register, so it cannot conflict with anything else.
+            unsafe { Vic::new() }.disable_interrupt(interrupt);
+            return;
+        }
+        // Safety: only `register` stores to HANDLERS, and it stores a `Handler`
+        let handler: Handler = unsafe { core::mem::transmute(handler) };
+        handler(ctx);
+    }
 }
 
 impl<const ADDR: usize> Interrupt<ADDR> {
@@ -25,55 +241,144 @@ impl<const ADDR: usize> Interrupt<ADDR> {
     // These are in 32-bit word offsets (so * 4 to get byte offsets)
 
     const IRQ_STATUS_OFFSET: usize = 0x00 >> 2;
+    const FIQ_STATUS_OFFSET: usize = 0x04 >> 2;
     const INT_SELECT_OFFSET: usize = 0x0C >> 2;
     const INT_EN_OFFSET: usize = 0x10 >> 2;
+    const INT_EN_CLEAR_OFFSET: usize = 0x14 >> 2;
+    const SOFT_INT_OFFSET: usize = 0x18 >> 2;
+    const SOFT_INT_CLEAR_OFFSET: usize = 0x1C >> 2;
+    const VECT_ADDR_OFFSET: usize = 0x30 >> 2;
     const DEF_VECT_ADDR_OFFSET: usize = 0x34 >> 2;
+    const VECT_ADDR_N_START_OFFSET: usize = 0x100 >> 2;
     const VECT_CTRL_N_START_OFFSET: usize = 0x200 >> 2;
 
     const CNTL_ENABLE: u32 = 1 << 5;
 
+    /// What VICVectAddr reads as when no vectored interrupt is active
+    const NO_VECTOR: u32 = 0;
+
     const NUM_PRIOS: u8 = 16;
     const NUM_IRQS: u8 = 32;
 
-    /// Get the address of the control register for a particular interrupt vector.
-    const fn prio_control_addr(prio: u8) -> *mut u32 {
+    /// Get the offset of the control register for a particular interrupt vector.
+    const fn vect_ctrl_offset(prio: u8) -> usize {
         if prio >= Self::NUM_PRIOS {
             panic!("bad prio");
         }
-        unsafe { Self::BASE_PTR.add(Self::VECT_CTRL_N_START_OFFSET + (prio as usize)) }
+        Self::VECT_CTRL_N_START_OFFSET + (prio as usize)
     }
 
-    /// Initialise the interrupt controller by setting up all the vectors
+    /// Get the offset of the address register for a particular interrupt vector.
+    const fn vect_addr_offset(prio: u8) -> usize {
+        if prio >= Self::NUM_PRIOS {
+            panic!("bad prio");
+        }
+        Self::VECT_ADDR_N_START_OFFSET + (prio as usize)
+    }
+
+    /// Initialise the interrupt controller, with every interrupt disabled and
+    /// no vectors in use
     pub fn init(&mut self) {
-        unsafe {
-            // Set the first 16 vectors to point to the first 16 sources
-            for i in 0..Self::NUM_PRIOS {
-                Self::prio_control_addr(i).write_volatile(Self::CNTL_ENABLE | u32::from(i));
-            }
-            // Setup default vector - points at IRQ handler in vector table
-            Self::BASE_PTR
-                .add(Self::DEF_VECT_ADDR_OFFSET)
-                .write_volatile(0x18);
-            // Every interrupt is an IRQ not an FIQ
-            Self::BASE_PTR
-                .add(Self::INT_SELECT_OFFSET)
-                .write_volatile(0x0000_0000);
+        Self::write_reg(Self::INT_EN_CLEAR_OFFSET, 0xFFFF_FFFF);
+        Self::write_reg(Self::SOFT_INT_CLEAR_OFFSET, 0xFFFF_FFFF);
+        for i in 0..Self::NUM_PRIOS {
+            Self::write_reg(Self::vect_ctrl_offset(i), 0);
+            Self::write_reg(Self::vect_addr_offset(i), Self::NO_VECTOR);
         }
+        Self::write_reg(Self::DEF_VECT_ADDR_OFFSET, Self::NO_VECTOR);
+        // Every interrupt is an IRQ not an FIQ, until `register_fiq` says
+        // otherwise
+        Self::write_reg(Self::INT_SELECT_OFFSET, 0x0000_0000);
+        // Nothing goes from the SIC to the VIC, except through VIC input 31
+        Sic::write_reg(Sic::ENABLE_CLEAR_OFFSET, 0xFFFF_FFFF);
+        Sic::write_reg(Sic::SOFT_INT_CLEAR_OFFSET, 0xFFFF_FFFF);
+        Sic::write_reg(Sic::PIC_ENABLE_CLEAR_OFFSET, 0xFFFF_FFFF);
     }
 
+    /// Enable an interrupt. SIC inputs are enabled in the SIC, and the SIC's
+    /// own VIC input is enabled too.
     pub fn enable_interrupt(&mut self, interrupt: u8) {
-        if interrupt > Self::NUM_IRQS {
-            panic!("Bad IRQ");
+        match interrupt.checked_sub(SIC_OFFSET) {
+            Some(input) if input < Sic::NUM_IRQS => {
+                Sic::write_reg(Sic::ENABLE_SET_OFFSET, 1 << input);
+                Self::write_reg(Self::INT_EN_OFFSET, 1 << SIC_VIC_INPUT);
+            }
+            Some(_) => panic!("Bad IRQ"),
+            None => Self::write_reg(Self::INT_EN_OFFSET, 1 << interrupt),
         }
+    }
+
+    /// Disable an interrupt. For SIC inputs, only that input is disabled.
+    pub fn disable_interrupt(&mut self, interrupt: u8) {
+        match interrupt.checked_sub(SIC_OFFSET) {
+            Some(input) if input < Sic::NUM_IRQS => {
+                Sic::write_reg(Sic::ENABLE_CLEAR_OFFSET, 1 << input)
+            }
+            Some(_) => panic!("Bad IRQ"),
+            None => Self::write_reg(Self::INT_EN_CLEAR_OFFSET, 1 << interrupt),
+        }
+    }
 
-        unsafe {
-            Self::BASE_PTR
-                .add(Self::INT_EN_OFFSET)
-                .write_volatile(1 << interrupt);
+    /// Raise an interrupt from software.
+    ///
+    /// [`Vic::dispatch`] clears it again before calling the handler.
+    pub fn trigger(interrupt: u8) {
+        match interrupt.checked_sub(SIC_OFFSET) {
+            Some(input) if input < Sic::NUM_IRQS => {
+                Sic::write_reg(Sic::SOFT_INT_SET_OFFSET, 1 << input)
+            }
+            Some(_) => panic!("Bad IRQ"),
+            None if interrupt < Self::NUM_IRQS => {
+                Self::write_reg(Self::SOFT_INT_OFFSET, 1 << interrupt)
+            }
+            None => panic!("Bad IRQ"),
         }
     }
 
     pub fn read_interrupt_status() -> u32 {
-        unsafe { Self::BASE_PTR.add(Self::IRQ_STATUS_OFFSET).read_volatile() }
+        Self::read_reg(Self::IRQ_STATUS_OFFSET)
+    }
+
+    /// Read a register
+    fn read_reg(offset: usize) -> u32 {
+        unsafe { Self::BASE_PTR.add(offset).read_volatile() }
+    }
+
+    /// Write a register
+    fn write_reg(offset: usize, value: u32) {
+        unsafe { Self::BASE_PTR.add(offset).write_volatile(value) }
     }
 }
+
+/// The Secondary Interrupt Controller on an Arm Versatile Application Board
+struct Sic;
+
+impl Sic {
+    const BASE_PTR: *mut u32 = 0x1000_3000 as *mut u32;
+
+    // These are in 32-bit word offsets (so * 4 to get byte offsets)
+
+    const STATUS_OFFSET: usize = 0x00 >> 2;
+    const ENABLE_SET_OFFSET: usize = 0x08 >> 2;
+    const ENABLE_CLEAR_OFFSET: usize = 0x0C >> 2;
+    const SOFT_INT_SET_OFFSET: usize = 0x10 >> 2;
+    const SOFT_INT_CLEAR_OFFSET: usize = 0x14 >> 2;
+    const PIC_ENABLE_CLEAR_OFFSET: usize = 0x24 >> 2;
+
+    /// Reading SIC_SOFTINTSET gives the software interrupt status
+    const SOFT_INT_OFFSET: usize = Self::SOFT_INT_SET_OFFSET;
+
+    const NUM_IRQS: u8 = 32;
+
+    /// Read a register
+    fn read_reg(offset: usize) -> u32 {
+        unsafe { Self::BASE_PTR.add(offset).read_volatile() }
+    }
+
+    /// Write a register
+    fn write_reg(offset: usize, value: u32) {
+        unsafe { Self::BASE_PTR.add(offset).write_volatile(value) }
+    }
+}
+
+// End of file
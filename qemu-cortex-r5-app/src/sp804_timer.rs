@@ -1,23 +1,90 @@
-//! Code for the Arm SP804 Timer
+//! Code for the Arm SP804 Dual Timer
+//!
+//! The Arm Versatile Application Board has two SP804 blocks, each with two
+//! counters. Timer0 and Timer1 share one interrupt controller input, as do
+//! Timer2 and Timer3.
 
 // SPDX-FileCopyrightText: Copyright (c) 2024 Ferrous Systems
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+/// How fast the timers count, before any prescaling
+pub const TIMER_CLOCK_HZ: u32 = 1_000_000;
+
 /// Supported timer modes
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
 pub enum Mode {
-    AutoReload = 0,
-    SingleShot = 1,
+    /// Count down to zero, interrupt, and start again from the load value
+    AutoReload,
+    /// Count down to zero, interrupt, and stop
+    SingleShot,
+    /// Count down to zero, interrupt, and carry on from the largest value
+    FreeRunning,
 }
 
 /// Supported interrupt options
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
 pub enum Interrupts {
-    Disabled = 0,
-    Enabled = 1 << 5,
+    Disabled,
+    Enabled,
+}
+
+/// Supported counter sizes
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum Size {
+    Bits16,
+    Bits32,
+}
+
+/// Supported clock dividers
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum Prescale {
+    Div1,
+    Div16,
+    Div256,
 }
 
+/// Everything you can set up on a timer
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub struct Config {
+    /// What the timer counts down from
+    pub load_value: u32,
+    pub mode: Mode,
+    pub interrupts: Interrupts,
+    pub size: Size,
+    pub prescale: Prescale,
+}
+
+impl Config {
+    /// A 32-bit timer, counting at [`TIMER_CLOCK_HZ`]
+    pub const fn new(load_value: u32, mode: Mode, interrupts: Interrupts) -> Config {
+        Config {
+            load_value,
+            mode,
+            interrupts,
+            size: Size::Bits32,
+            prescale: Prescale::Div1,
+        }
+    }
+}
+
+/// The interrupt controller input shared by Timer0 and Timer1
+pub const TIMER01_INTERRUPT: u8 = 4;
+
+/// The interrupt controller input shared by Timer2 and Timer3
+pub const TIMER23_INTERRUPT: u8 = 5;
+
 /// Timer0 on an Arm Versatile Application Board.
 pub type Timer0 = Timer<0x101e_2000>;
 
+/// Timer1 on an Arm Versatile Application Board.
+pub type Timer1 = Timer<0x101e_2020>;
+
+/// Timer2 on an Arm Versatile Application Board.
+pub type Timer2 = Timer<0x101e_3000>;
+
+/// Timer3 on an Arm Versatile Application Board.
+pub type Timer3 = Timer<0x101e_3020>;
+
 /// A driver for a virtual SP804 Timer
 ///
 /// It probably skips some important initialisation, but it works on QEMU.
@@ -34,54 +101,165 @@ impl Timer0 {
     }
 }
 
+impl Timer1 {
+    /// Create a new Timer object for Timer1
+    ///
+    /// # Safety
+    ///
+    /// Only construct one object per Timer at any given time.
+    pub unsafe fn new_timer1() -> Self {
+        Timer()
+    }
+}
+
+impl Timer2 {
+    /// Create a new Timer object for Timer2
+    ///
+    /// # Safety
+    ///
+    /// Only construct one object per Timer at any given time.
+    pub unsafe fn new_timer2() -> Self {
+        Timer()
+    }
+}
+
+impl Timer3 {
+    /// Create a new Timer object for Timer3
+    ///
+    /// # Safety
+    ///
+    /// Only construct one object per Timer at any given time.
+    pub unsafe fn new_timer3() -> Self {
+        Timer()
+    }
+}
+
 impl<const ADDR: usize> Timer<ADDR> {
     const BASE_PTR: *mut u32 = ADDR as *mut u32;
 
-    const LOAD_REGISTER: usize = 0x00 >> 2;
+    // These are in 32-bit word offsets (so * 4 to get byte offsets)
+
+    const LOAD_OFFSET: usize = 0x00 >> 2;
+    const VALUE_OFFSET: usize = 0x04 >> 2;
     const CTRL_OFFSET: usize = 0x08 >> 2;
     const ICR_OFFSET: usize = 0x0C >> 2;
+    const RIS_OFFSET: usize = 0x10 >> 2;
     const MIS_OFFSET: usize = 0x14 >> 2;
+    const BG_LOAD_OFFSET: usize = 0x18 >> 2;
 
+    const CTRL_ONESHOT: u32 = 1 << 0;
     const CTRL_TIMERSIZE_32: u32 = 1 << 1;
+    const CTRL_TIMERPRE_16: u32 = 0b01 << 2;
+    const CTRL_TIMERPRE_256: u32 = 0b10 << 2;
+    const CTRL_INTENABLE: u32 = 1 << 5;
     const CTRL_TIMERMODE: u32 = 1 << 6;
     const CTRL_TIMEREN: u32 = 1 << 7;
 
-    /// Initialise the timer
-    pub fn init(&mut self, load_value: u32, mode: Mode, interrupt: Interrupts) {
-        unsafe {
-            Self::BASE_PTR
-                .add(Self::LOAD_REGISTER)
-                .write_volatile(load_value);
-            let settings =
-                Self::CTRL_TIMERSIZE_32 | Self::CTRL_TIMERMODE | mode as u32 | interrupt as u32;
-            Self::BASE_PTR
-                .add(Self::CTRL_OFFSET)
-                .write_volatile(settings);
+    /// Initialise a stopped 32-bit timer
+    pub fn init(&mut self, load_value: u32, mode: Mode, interrupts: Interrupts) {
+        self.configure(&Config::new(load_value, mode, interrupts));
+    }
+
+    /// Set everything up, leaving the timer stopped
+    pub fn configure(&mut self, config: &Config) {
+        Self::write_reg(Self::CTRL_OFFSET, 0);
+        Self::clear_interrupt();
+        Self::write_reg(Self::LOAD_OFFSET, config.load_value);
+        let mut settings = Self::mode_bits(config.mode);
+        settings |= match config.size {
+            Size::Bits16 => 0,
+            Size::Bits32 => Self::CTRL_TIMERSIZE_32,
+        };
+        settings |= match config.prescale {
+            Prescale::Div1 => 0,
+            Prescale::Div16 => Self::CTRL_TIMERPRE_16,
+            Prescale::Div256 => Self::CTRL_TIMERPRE_256,
+        };
+        if config.interrupts == Interrupts::Enabled {
+            settings |= Self::CTRL_INTENABLE;
         }
+        Self::write_reg(Self::CTRL_OFFSET, settings);
     }
 
     /// Start the timer
     pub fn start(&mut self) {
-        unsafe {
-            let time1_ctrl = Self::BASE_PTR.add(2);
-            let mut temp = time1_ctrl.read_volatile();
-            temp |= Self::CTRL_TIMEREN;
-            time1_ctrl.write_volatile(temp);
-        }
+        Self::modify_ctrl(Self::CTRL_TIMEREN, true);
+    }
+
+    /// Stop the timer, leaving its value where it is
+    pub fn stop(&mut self) {
+        Self::modify_ctrl(Self::CTRL_TIMEREN, false);
+    }
+
+    /// Change the mode, leaving the count and any pending interrupt alone
+    pub fn set_mode(&mut self, mode: Mode) {
+        let ctrl = Self::read_reg(Self::CTRL_OFFSET) & !(Self::CTRL_ONESHOT | Self::CTRL_TIMERMODE);
+        Self::write_reg(Self::CTRL_OFFSET, ctrl | Self::mode_bits(mode));
+    }
+
+    /// Turn the timer's interrupt on or off
+    pub fn set_interrupts(&mut self, interrupts: Interrupts) {
+        Self::modify_ctrl(Self::CTRL_INTENABLE, interrupts == Interrupts::Enabled);
     }
 
+    /// Set the load value, and restart the count from it immediately
+    pub fn set_load(&mut self, load_value: u32) {
+        Self::write_reg(Self::LOAD_OFFSET, load_value);
+    }
+
+    /// Set the load value, without disturbing the current count.
+    ///
+    /// An auto-reloading timer uses it the next time it reaches zero.
+    pub fn set_background_load(&mut self, load_value: u32) {
+        Self::write_reg(Self::BG_LOAD_OFFSET, load_value);
+    }
+
+    /// Read the current count
+    pub fn value() -> u32 {
+        Self::read_reg(Self::VALUE_OFFSET)
+    }
+
+    /// Has the timer reached zero, with its interrupt enabled?
     pub fn is_pending() -> bool {
-        let value = unsafe { Self::BASE_PTR.add(Self::MIS_OFFSET).read_volatile() };
-        (value & 1) != 0
+        (Self::read_reg(Self::MIS_OFFSET) & 1) != 0
+    }
+
+    /// Has the timer reached zero, whether or not its interrupt is enabled?
+    pub fn is_raw_pending() -> bool {
+        (Self::read_reg(Self::RIS_OFFSET) & 1) != 0
     }
 
     /// Clear a pending interrupt
     pub fn clear_interrupt() {
         // Write anything here to clear the interrupt
-        unsafe {
-            Self::BASE_PTR.add(Self::ICR_OFFSET).write_volatile(1);
+        Self::write_reg(Self::ICR_OFFSET, 1);
+    }
+
+    /// The control register bits for a mode
+    const fn mode_bits(mode: Mode) -> u32 {
+        match mode {
+            Mode::AutoReload => Self::CTRL_TIMERMODE,
+            Mode::SingleShot => Self::CTRL_ONESHOT,
+            Mode::FreeRunning => 0,
         }
     }
+
+    /// Set or clear some bits in the control register
+    fn modify_ctrl(bits: u32, set: bool) {
+        let ctrl = Self::read_reg(Self::CTRL_OFFSET);
+        let ctrl = if set { ctrl | bits } else { ctrl & !bits };
+        Self::write_reg(Self::CTRL_OFFSET, ctrl);
+    }
+
+    /// Read a register
+    fn read_reg(offset: usize) -> u32 {
+        unsafe { Self::BASE_PTR.add(offset).read_volatile() }
+    }
+
+    /// Write a register
+    fn write_reg(offset: usize, value: u32) {
+        unsafe { Self::BASE_PTR.add(offset).write_volatile(value) }
+    }
 }
 
 // End of file
@@ -0,0 +1,149 @@
+//! Tickless idle for ThreadX, using SP804 Timer0
+//!
+//! Normally Timer0 interrupts once per ThreadX tick, even when every thread
+//! is asleep. Instead, when the scheduler has nothing to run, ThreadX's
+//! `tx_low_power_enter` works out how many ticks there are until the next
+//! timer expires, and calls [`tickless_timer_setup`] to set Timer0 to
+//! interrupt once after that long. [`tickless_enter`] then waits for an
+//! interrupt. When anything wakes the CPU, `tx_low_power_exit` calls
+//! [`tickless_exit`], which works out how many ticks went by and puts Timer0
+//! back to interrupting every tick, and [`tickless_timer_adjust`], which tells
+//! ThreadX how many ticks to add to its clock.
+//!
+//! ThreadX must be built with `TX_LOW_POWER`, with the
+//! `TX_LOW_POWER_TIMER_SETUP`, `TX_LOW_POWER_USER_ENTER`,
+//! `TX_LOW_POWER_USER_EXIT` and `TX_LOW_POWER_USER_TIMER_ADJUST` hooks
+//! pointing at these functions, and with a scheduler which calls
+//! `tx_low_power_enter` and `tx_low_power_exit` when it is idle. Our
+//! `build.rs` does all of that.
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::sp804_timer::{Mode, Timer0};
+
+/// How many Timer0 counts there are in a tick
+static TICK_COUNTS: AtomicU32 = AtomicU32::new(0);
+
+/// What Timer0 was loaded with for this sleep, and how many counts of the
+/// tick we were in had already gone by
+static SLEEP: [AtomicU32; 2] = [AtomicU32::new(0), AtomicU32::new(0)];
+
+/// Set whilst Timer0 is set to interrupt once, rather than every tick
+static SLEEPING: AtomicBool = AtomicBool::new(false);
+
+/// How many ticks went by whilst we slept, which ThreadX has not been told
+/// about yet
+static SKIPPED: AtomicU32 = AtomicU32::new(0);
+
+/// How many times we have slept, and how many ticks we skipped doing so
+static STATS: [AtomicU32; 2] = [AtomicU32::new(0), AtomicU32::new(0)];
+
+/// How much sleeping we have done
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub struct Stats {
+    /// How many times Timer0 was stopped from ticking
+    pub sleeps: u32,
+    /// How many tick interrupts that saved
+    pub ticks_skipped: u32,
+}
+
+/// Set up tickless idle.
+///
+/// Timer0 must already be interrupting every `tick_counts` counts, in
+/// [`Mode::AutoReload`].
+pub fn init(tick_counts: u32) {
+    TICK_COUNTS.store(tick_counts, Ordering::Relaxed);
+}
+
+/// Get how much sleeping we have done
+pub fn stats() -> Stats {
+    Stats {
+        sleeps: STATS[0].load(Ordering::Relaxed),
+        ticks_skipped: STATS[1].load(Ordering::Relaxed),
+    }
+}
+
+/// Set Timer0 to interrupt once, `ticks` ticks from the last one.
+///
+/// Called by `tx_low_power_enter`, with interrupts disabled. If there's a
+/// tick waiting to be handled, or the next tick is when something happens
+/// anyway, Timer0 is left alone.
+#[no_mangle]
+pub extern "C" fn tickless_timer_setup(ticks: u32) {
+    if Timer0::is_raw_pending() || ticks <= 1 {
+        return;
+    }
+    let tick_counts = TICK_COUNTS.load(Ordering::Relaxed);
+    // Timer0 is part way through a tick
+    let gone = tick_counts - Timer0::value().min(tick_counts);
+    let ticks = ticks.min(u32::MAX / tick_counts);
+    let load = ticks * tick_counts - gone;
+    SLEEP[0].store(load, Ordering::Relaxed);
+    SLEEP[1].store(gone, Ordering::Relaxed);
+    SLEEPING.store(true, Ordering::Relaxed);
+    // Safety: Timer0 is only reprogrammed here and in `wake`, with interrupts
+    // disabled, and the tick handler only clears its interrupt
+    let mut timer = unsafe { Timer0::new_timer0() };
+    timer.stop();
+    timer.set_mode(Mode::SingleShot);
+    timer.set_load(load);
+    timer.start();
+}
+
+/// Wait for an interrupt.
+///
+/// Called by `tx_low_power_enter`, with interrupts disabled, which a pending
+/// interrupt still wakes us from.
+#[no_mangle]
+pub extern "C" fn tickless_enter() {
+    unsafe { core::arch::asm!("wfi") };
+}
+
+/// Put Timer0 back to interrupting every tick, if it was stopped.
+///
+/// Called by `tx_low_power_exit`, with interrupts still disabled, so that
+/// ThreadX has the right time before any interrupt handler runs.
+#[no_mangle]
+pub extern "C" fn tickless_exit() {
+    if SLEEPING.swap(false, Ordering::Relaxed) {
+        SKIPPED.store(wake(), Ordering::Relaxed);
+    }
+}
+
+/// Get how many ticks ThreadX missed whilst we slept.
+///
+/// Called by `tx_low_power_exit`, which adds them to the ThreadX clock.
+#[no_mangle]
+pub extern "C" fn tickless_timer_adjust() -> u32 {
+    SKIPPED.swap(0, Ordering::Relaxed)
+}
+
+/// Put Timer0 back to interrupting every tick, returning how many ticks went
+/// by which the tick handler won't be told about
+fn wake() -> u32 {
+    let tick_counts = TICK_COUNTS.load(Ordering::Relaxed);
+    let load = SLEEP[0].load(Ordering::Relaxed);
+    let gone = SLEEP[1].load(Ordering::Relaxed);
+    let mut timer = unsafe { Timer0::new_timer0() };
+    timer.stop();
+    let (ticks, into_tick) = if Timer0::is_raw_pending() {
+        // We slept the whole time. The tick handler still has the interrupt
+        // to deal with, and counts the last tick itself.
+        ((load + gone) / tick_counts - 1, 0)
+    } else {
+        let counts = gone + (load - Timer0::value());
+        (counts / tick_counts, counts % tick_counts)
+    };
+    timer.set_mode(Mode::AutoReload);
+    timer.set_load(tick_counts - into_tick);
+    timer.set_background_load(tick_counts);
+    timer.start();
+    STATS[0].fetch_add(1, Ordering::Relaxed);
+    STATS[1].fetch_add(ticks, Ordering::Relaxed);
+    ticks
+}
+
+// End of file
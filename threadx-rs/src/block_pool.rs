@@ -0,0 +1,170 @@
+//! ThreadX block pools
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::UnsafeCell,
+    ffi::CStr,
+    marker::PhantomPinned,
+    pin::Pin,
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use threadx_sys::{TX_BLOCK_POOL, ULONG};
+
+use crate::{
+    context::{Context, ThreadContext},
+    Error, Wait,
+};
+
+/// Work out how much memory a block pool needs.
+///
+/// ThreadX rounds each block up to a whole number of ULONGs, and keeps a
+/// pointer in front of each one.
+pub const fn pool_bytes(block_size: usize, block_count: usize) -> usize {
+    let word = core::mem::size_of::<ULONG>();
+    let block = block_size.div_ceil(word) * word;
+    (block + core::mem::size_of::<*mut u8>()) * block_count
+}
+
+/// A ThreadX block pool, from which fixed-sized blocks can be allocated.
+#[repr(transparent)]
+pub struct BlockPool {
+    inner: UnsafeCell<TX_BLOCK_POOL>,
+    _pin: PhantomPinned,
+}
+
+// Safety: every access to the inner control block goes via a ThreadX API,
+// which handles the locking.
+unsafe impl Sync for BlockPool {}
+
+impl BlockPool {
+    /// Create an empty (all-zero) control block.
+    const fn zeroed() -> BlockPool {
+        BlockPool {
+            // Safety: TX_BLOCK_POOL is plain-old-data, and all zeroes is a
+            // valid value for every field.
+            inner: UnsafeCell::new(unsafe { core::mem::zeroed() }),
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Get a raw pointer to the ThreadX control block
+    pub fn as_ptr(&self) -> *mut TX_BLOCK_POOL {
+        self.inner.get()
+    }
+
+    /// Allocate a block from the pool, waiting for one to be released if
+    /// required.
+    ///
+    /// The memory is not initialised.
+    pub fn allocate(&self, _ctx: ThreadContext, wait: Wait) -> Result<NonNull<u8>, Error> {
+        self.allocate_raw(wait)
+    }
+
+    /// Allocate a block from the pool, if one is free.
+    ///
+    /// The memory is not initialised.
+    pub fn try_allocate(&self, _ctx: impl Context) -> Result<NonNull<u8>, Error> {
+        self.allocate_raw(Wait::NONE)
+    }
+
+    fn allocate_raw(&self, wait: Wait) -> Result<NonNull<u8>, Error> {
+        let mut block = core::ptr::null_mut();
+        Error::check(unsafe {
+            threadx_sys::_tx_block_allocate(self.as_ptr(), &mut block, wait.as_raw())
+        })?;
+        NonNull::new(block as *mut u8).ok_or(Error::NoMemory)
+    }
+
+    /// Return a block to the pool it was allocated from.
+    ///
+    /// # Safety
+    ///
+    /// `block` must have come from [`BlockPool::allocate`] or
+    /// [`BlockPool::try_allocate`], and must not be
+    /// used after this call.
+    pub unsafe fn release(block: NonNull<u8>) -> Result<(), Error> {
+        Error::check(unsafe { threadx_sys::_tx_block_release(block.as_ptr() as *mut _) })
+    }
+}
+
+/// The memory managed by a block pool.
+#[repr(C, align(8))]
+struct Storage<const N: usize>([u8; N]);
+
+/// Storage for a block pool and its memory, suitable for placing in a
+/// `static`.
+///
+/// `BYTES` is the total size of the pool, as calculated by [`pool_bytes`].
+/// Use the [`static_block_pool!`](crate::static_block_pool) macro, which will
+/// work it out for you, rather than using this type directly.
+pub struct StaticBlockPool<const BLOCK_SIZE: usize, const BYTES: usize> {
+    pool: BlockPool,
+    storage: UnsafeCell<Storage<BYTES>>,
+    created: AtomicBool,
+}
+
+// Safety: the storage is only handed to ThreadX, once, guarded by `created`.
+unsafe impl<const BLOCK_SIZE: usize, const BYTES: usize> Sync
+    for StaticBlockPool<BLOCK_SIZE, BYTES>
+{
+}
+
+impl<const BLOCK_SIZE: usize, const BYTES: usize> StaticBlockPool<BLOCK_SIZE, BYTES> {
+    /// Reserve space for a block pool. It ends up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticBlockPool<BLOCK_SIZE, BYTES> {
+        StaticBlockPool {
+            pool: BlockPool::zeroed(),
+            storage: UnsafeCell::new(Storage([0u8; BYTES])),
+            created: AtomicBool::new(false),
+        }
+    }
+
+    /// Create the ThreadX block pool.
+    ///
+    /// Returns [`Error::PoolError`] if the pool has already been created.
+    pub fn create(&'static self, name: &'static CStr) -> Result<Pin<&'static BlockPool>, Error> {
+        if self.created.swap(true, Ordering::AcqRel) {
+            return Err(Error::PoolError);
+        }
+        let res = unsafe {
+            threadx_sys::_tx_block_pool_create(
+                self.pool.as_ptr(),
+                crate::name_ptr(name),
+                BLOCK_SIZE as ULONG,
+                self.storage.get() as *mut _,
+                BYTES as ULONG,
+            )
+        };
+        if let Err(e) = Error::check(res) {
+            self.created.store(false, Ordering::Release);
+            return Err(e);
+        }
+        Ok(Pin::static_ref(&self.pool))
+    }
+}
+
+/// Reserve a block pool and its memory in `.bss`, and create it.
+///
+/// Evaluates to `Result<Pin<&'static BlockPool>, Error>`.
+///
+/// ```rust,ignore
+/// // Eight blocks of 64 bytes each
+/// let pool = threadx::static_block_pool!(64, 8, c"block-pool0")?;
+/// ```
+#[macro_export]
+macro_rules! static_block_pool {
+    ($block_size:expr, $block_count:expr, $name:expr) => {{
+        static BLOCK_POOL: $crate::block_pool::StaticBlockPool<
+            { $block_size },
+            { $crate::block_pool::pool_bytes($block_size, $block_count) },
+        > = $crate::block_pool::StaticBlockPool::new();
+        BLOCK_POOL.create($name)
+    }};
+}
+
+// End of file
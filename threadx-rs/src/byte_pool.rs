@@ -0,0 +1,158 @@
+//! ThreadX byte pools
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::UnsafeCell,
+    ffi::CStr,
+    marker::PhantomPinned,
+    pin::Pin,
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use threadx_sys::{TX_BYTE_POOL, ULONG};
+
+use crate::{context::ThreadContext, Error, Wait};
+
+/// A ThreadX byte pool, from which variable-sized blocks can be allocated.
+#[repr(transparent)]
+pub struct BytePool {
+    inner: UnsafeCell<TX_BYTE_POOL>,
+    _pin: PhantomPinned,
+}
+
+// Safety: every access to the inner control block goes via a ThreadX API,
+// which handles the locking.
+unsafe impl Sync for BytePool {}
+
+impl BytePool {
+    /// Create an empty (all-zero) control block.
+    const fn zeroed() -> BytePool {
+        BytePool {
+            // Safety: TX_BYTE_POOL is plain-old-data, and all zeroes is a
+            // valid value for every field.
+            inner: UnsafeCell::new(unsafe { core::mem::zeroed() }),
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Get a raw pointer to the ThreadX control block
+    pub fn as_ptr(&self) -> *mut TX_BYTE_POOL {
+        self.inner.get()
+    }
+
+    /// Allocate `size` bytes from the pool.
+    ///
+    /// The memory is not initialised. ThreadX aligns every allocation to at
+    /// least a ULONG. Byte pools cannot be used from interrupts, even without
+    /// waiting.
+    pub fn allocate(
+        &self,
+        _ctx: ThreadContext,
+        size: usize,
+        wait: Wait,
+    ) -> Result<NonNull<u8>, Error> {
+        let mut memory = core::ptr::null_mut();
+        Error::check(unsafe {
+            threadx_sys::_tx_byte_allocate(self.as_ptr(), &mut memory, size as ULONG, wait.as_raw())
+        })?;
+        NonNull::new(memory as *mut u8).ok_or(Error::NoMemory)
+    }
+
+    /// Return some memory to the pool it was allocated from.
+    ///
+    /// # Safety
+    ///
+    /// `memory` must have come from [`BytePool::allocate`], and must not be
+    /// used after this call.
+    pub unsafe fn release(memory: NonNull<u8>) -> Result<(), Error> {
+        Error::check(unsafe { threadx_sys::_tx_byte_release(memory.as_ptr() as *mut _) })
+    }
+}
+
+/// The memory managed by a byte pool.
+#[repr(C, align(8))]
+struct Storage<const N: usize>([u8; N]);
+
+/// Storage for a byte pool and its memory, suitable for placing in a `static`.
+///
+/// Use the [`static_byte_pool!`](crate::static_byte_pool) macro rather than
+/// using this type directly.
+pub struct StaticBytePool<const SIZE: usize> {
+    pool: BytePool,
+    storage: UnsafeCell<Storage<SIZE>>,
+    created: AtomicBool,
+    ready: AtomicBool,
+}
+
+// Safety: the storage is only handed to ThreadX, once, guarded by `created`.
+unsafe impl<const SIZE: usize> Sync for StaticBytePool<SIZE> {}
+
+impl<const SIZE: usize> StaticBytePool<SIZE> {
+    /// Reserve space for a byte pool. It ends up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticBytePool<SIZE> {
+        StaticBytePool {
+            pool: BytePool::zeroed(),
+            storage: UnsafeCell::new(Storage([0u8; SIZE])),
+            created: AtomicBool::new(false),
+            ready: AtomicBool::new(false),
+        }
+    }
+
+    /// Get the byte pool, if it has been created.
+    ///
+    /// Useful when the pool lives in a `static` of its own, and the code
+    /// using it cannot be given the handle returned by
+    /// [`create`](Self::create).
+    pub fn get(&'static self) -> Option<Pin<&'static BytePool>> {
+        if self.ready.load(Ordering::Acquire) {
+            Some(Pin::static_ref(&self.pool))
+        } else {
+            None
+        }
+    }
+
+    /// Create the ThreadX byte pool.
+    ///
+    /// Returns [`Error::PoolError`] if the pool has already been created.
+    pub fn create(&'static self, name: &'static CStr) -> Result<Pin<&'static BytePool>, Error> {
+        if self.created.swap(true, Ordering::AcqRel) {
+            return Err(Error::PoolError);
+        }
+        let res = unsafe {
+            threadx_sys::_tx_byte_pool_create(
+                self.pool.as_ptr(),
+                crate::name_ptr(name),
+                self.storage.get() as *mut _,
+                SIZE as ULONG,
+            )
+        };
+        if let Err(e) = Error::check(res) {
+            self.created.store(false, Ordering::Release);
+            return Err(e);
+        }
+        self.ready.store(true, Ordering::Release);
+        Ok(Pin::static_ref(&self.pool))
+    }
+}
+
+/// Reserve a byte pool and its memory in `.bss`, and create it.
+///
+/// Evaluates to `Result<Pin<&'static BytePool>, Error>`.
+///
+/// ```rust,ignore
+/// let pool = threadx::static_byte_pool!(32768, c"byte-pool0")?;
+/// ```
+#[macro_export]
+macro_rules! static_byte_pool {
+    ($size:expr, $name:expr) => {{
+        static BYTE_POOL: $crate::byte_pool::StaticBytePool<{ $size }> =
+            $crate::byte_pool::StaticBytePool::new();
+        BYTE_POOL.create($name)
+    }};
+}
+
+// End of file
@@ -0,0 +1,196 @@
+//! Working out which context we are executing in
+//!
+//! ThreadX services which can block may only be called from a thread. Other
+//! services may also be called from an interrupt handler (or a timer expiry
+//! function). We enforce this at compile time with tokens: a
+//! [`ThreadContext`] proves we are in a thread, and an [`IsrContext`] proves
+//! we are in an interrupt handler. Services which can block take a
+//! `ThreadContext`, and services which never block take anything that
+//! implements [`Context`].
+//!
+//! The tokens cannot be sent between contexts, or stored in a `static`.
+//!
+//! ```rust,ignore
+//! extern "C" fn my_thread(_input: u32) {
+//!     let ctx = ThreadContext::get().expect("Not in a thread");
+//!     let message = QUEUE.receive(ctx, Wait::FOREVER).unwrap();
+//! }
+//!
+//! #[no_mangle]
+//! unsafe extern "C" fn handle_interrupt() {
+//!     let ctx = unsafe { IsrContext::new_unchecked() };
+//!     let _ = QUEUE.try_send(ctx, 42);
+//! }
+//! ```
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{marker::PhantomData, ptr::NonNull};
+
+use threadx_sys::{TX_THREAD, UINT, ULONG};
+
+extern "C" {
+    /// Non-zero during initialisation, and whilst handling an interrupt.
+    static _tx_thread_system_state: ULONG;
+
+    /// The thread which runs timer expiry functions
+    static mut _tx_timer_thread: TX_THREAD;
+}
+
+/// Get the ThreadX system state, where zero means we are in a thread.
+///
+/// This is the equivalent of the `TX_THREAD_GET_SYSTEM_STATE()` macro.
+pub(crate) fn system_state() -> ULONG {
+    let state = unsafe { core::ptr::addr_of!(_tx_thread_system_state).read_volatile() };
+    // The Cortex-M ports do not count interrupts in the system state, so we
+    // must also check the IPSR, as the port's macro does.
+    #[cfg(all(target_arch = "arm", target_feature = "mclass"))]
+    let state = state
+        | {
+            let ipsr: u32;
+            unsafe {
+                core::arch::asm!("mrs {}, IPSR", out(reg) ipsr, options(nomem, nostack, preserves_flags));
+            }
+            ipsr as ULONG
+        };
+    state
+}
+
+/// Get the thread we are executing in.
+///
+/// Returns `None` during initialisation and in interrupt context, where
+/// `_tx_thread_identify` would return whichever thread was interrupted. Also
+/// returns `None` in a timer expiry function, which runs on ThreadX's timer
+/// thread - if that blocked, no timer (or sleep) would ever expire again.
+pub(crate) fn current_thread() -> Option<NonNull<TX_THREAD>> {
+    if system_state() != 0 {
+        return None;
+    }
+    let thread = NonNull::new(unsafe { threadx_sys::_tx_thread_identify() })?;
+    if thread.as_ptr() == core::ptr::addr_of_mut!(_tx_timer_thread) {
+        return None;
+    }
+    Some(thread)
+}
+
+/// Proof that we are executing in a ThreadX thread.
+///
+/// Required by every service which may block.
+#[derive(Debug, Copy, Clone)]
+pub struct ThreadContext {
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ThreadContext {
+    /// Get a token, if we are executing in a thread.
+    ///
+    /// Returns `None` during initialisation, in interrupt context and in timer
+    /// expiry functions.
+    pub fn get() -> Option<ThreadContext> {
+        current_thread().map(|_| ThreadContext {
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Get a token without checking.
+    ///
+    /// # Safety
+    ///
+    /// We must be executing in a ThreadX thread.
+    pub const unsafe fn new_unchecked() -> ThreadContext {
+        ThreadContext {
+            _not_send: PhantomData,
+        }
+    }
+}
+
+/// Proof that we are executing in an interrupt handler, or a timer expiry
+/// function.
+///
+/// Services which never block accept this token.
+#[derive(Debug, Copy, Clone)]
+pub struct IsrContext {
+    _not_send: PhantomData<*const ()>,
+}
+
+impl IsrContext {
+    /// Get a token without checking.
+    ///
+    /// Call this at the top of your interrupt handler.
+    ///
+    /// # Safety
+    ///
+    /// We must be executing in an interrupt handler which ThreadX knows
+    /// about, or in a timer expiry function.
+    pub const unsafe fn new_unchecked() -> IsrContext {
+        IsrContext {
+            _not_send: PhantomData,
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::ThreadContext {}
+    impl Sealed for super::IsrContext {}
+}
+
+/// Implemented by every context token.
+///
+/// Services which never block take an `impl Context`, so they can be called
+/// from a thread or from an interrupt.
+pub trait Context: sealed::Sealed + Copy {}
+
+impl Context for ThreadContext {}
+
+impl Context for IsrContext {}
+
+/// The interrupt posture from before [`disable_interrupts`] was called
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InterruptPosture(UINT);
+
+/// Disable interrupts, returning what [`restore_interrupts`] must put back.
+///
+/// Use [`without_interrupts`] instead, unless interrupts have to stay
+/// disabled across more than one function call.
+pub fn disable_interrupts() -> InterruptPosture {
+    InterruptPosture(unsafe {
+        threadx_sys::_tx_thread_interrupt_control(threadx_sys::TX_INT_DISABLE)
+    })
+}
+
+/// Put interrupts back the way they were before [`disable_interrupts`].
+///
+/// # Safety
+///
+/// `posture` must have come from the most recent call to
+/// [`disable_interrupts`] in this context which has not yet been restored,
+/// or interrupts could be enabled inside someone else's critical section.
+pub unsafe fn restore_interrupts(posture: InterruptPosture) {
+    unsafe {
+        threadx_sys::_tx_thread_interrupt_control(posture.0);
+    }
+}
+
+/// Run a closure with interrupts disabled, so that nothing else can touch the
+/// kernel's data structures whilst it runs.
+///
+/// The previous interrupt posture is restored afterwards, so this may be
+/// nested, and may be called from a thread or an interrupt handler.
+pub fn without_interrupts<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let posture = disable_interrupts();
+    let result = f();
+    // Safety: we restore the posture we just saved, after anything nested
+    // inside `f` has restored its own
+    unsafe {
+        restore_interrupts(posture);
+    }
+    result
+}
+
+// End of file
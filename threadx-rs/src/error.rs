@@ -0,0 +1,138 @@
+//! Errors reported by the ThreadX services
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use threadx_sys::UINT;
+
+/// A non-successful status code from a ThreadX service.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// Object was deleted while the caller was suspended on it
+    Deleted,
+    /// Invalid memory pool pointer
+    PoolError,
+    /// Invalid pointer
+    PtrError,
+    /// Wait option used from a non-thread context
+    WaitError,
+    /// Invalid size
+    SizeError,
+    /// Invalid event flags group pointer
+    GroupError,
+    /// Requested event flags not present
+    NoEvents,
+    /// Invalid option
+    OptionError,
+    /// Invalid queue pointer, or the queue was already created
+    QueueError,
+    /// Queue is empty
+    QueueEmpty,
+    /// Queue is full
+    QueueFull,
+    /// Invalid semaphore pointer, or the semaphore was already created
+    SemaphoreError,
+    /// Semaphore count was zero
+    NoInstance,
+    /// Invalid thread pointer, or the thread was already created
+    ThreadError,
+    /// Invalid thread priority
+    PriorityError,
+    /// Memory could not be allocated
+    NoMemory,
+    /// Object is not in a state where it can be deleted
+    DeleteError,
+    /// Thread was not suspended
+    ResumeError,
+    /// Invalid caller of this service
+    CallerError,
+    /// Thread is in a state where it cannot be suspended
+    SuspendError,
+    /// Invalid timer pointer, or the timer was already created
+    TimerError,
+    /// Invalid tick value
+    TickError,
+    /// Invalid timer activation selection
+    ActivateError,
+    /// Invalid preemption threshold
+    ThreshError,
+    /// Delayed suspension was lifted
+    SuspendLifted,
+    /// Suspension was aborted by another thread, timer or ISR
+    WaitAborted,
+    /// Thread was not in a suspended state
+    WaitAbortError,
+    /// Invalid mutex pointer, or the mutex was already created
+    MutexError,
+    /// Service was unable to get ownership of the object
+    NotAvailable,
+    /// Object is not owned by the caller
+    NotOwned,
+    /// Invalid priority inheritance selection
+    InheritError,
+    /// Operation did not complete
+    NotDone,
+    /// Semaphore ceiling was exceeded
+    CeilingExceeded,
+    /// Invalid semaphore ceiling value
+    InvalidCeiling,
+    /// Requested feature is not enabled in this build of ThreadX
+    FeatureNotEnabled,
+    /// Blocking would never return, because the caller already holds the lock,
+    /// or (in debug builds) because the lock's owner is waiting for a mutex
+    /// the caller holds
+    WouldDeadlock,
+    /// Thread was terminated before it could return a value
+    Terminated,
+    /// A status code we do not recognise
+    Unknown(UINT),
+}
+
+impl Error {
+    /// Convert a ThreadX status code into a `Result`.
+    pub fn check(status: UINT) -> Result<(), Error> {
+        let error = match status {
+            threadx_sys::TX_SUCCESS => return Ok(()),
+            threadx_sys::TX_DELETED => Error::Deleted,
+            threadx_sys::TX_POOL_ERROR => Error::PoolError,
+            threadx_sys::TX_PTR_ERROR => Error::PtrError,
+            threadx_sys::TX_WAIT_ERROR => Error::WaitError,
+            threadx_sys::TX_SIZE_ERROR => Error::SizeError,
+            threadx_sys::TX_GROUP_ERROR => Error::GroupError,
+            threadx_sys::TX_NO_EVENTS => Error::NoEvents,
+            threadx_sys::TX_OPTION_ERROR => Error::OptionError,
+            threadx_sys::TX_QUEUE_ERROR => Error::QueueError,
+            threadx_sys::TX_QUEUE_EMPTY => Error::QueueEmpty,
+            threadx_sys::TX_QUEUE_FULL => Error::QueueFull,
+            threadx_sys::TX_SEMAPHORE_ERROR => Error::SemaphoreError,
+            threadx_sys::TX_NO_INSTANCE => Error::NoInstance,
+            threadx_sys::TX_THREAD_ERROR => Error::ThreadError,
+            threadx_sys::TX_PRIORITY_ERROR => Error::PriorityError,
+            threadx_sys::TX_NO_MEMORY => Error::NoMemory,
+            threadx_sys::TX_DELETE_ERROR => Error::DeleteError,
+            threadx_sys::TX_RESUME_ERROR => Error::ResumeError,
+            threadx_sys::TX_CALLER_ERROR => Error::CallerError,
+            threadx_sys::TX_SUSPEND_ERROR => Error::SuspendError,
+            threadx_sys::TX_TIMER_ERROR => Error::TimerError,
+            threadx_sys::TX_TICK_ERROR => Error::TickError,
+            threadx_sys::TX_ACTIVATE_ERROR => Error::ActivateError,
+            threadx_sys::TX_THRESH_ERROR => Error::ThreshError,
+            threadx_sys::TX_SUSPEND_LIFTED => Error::SuspendLifted,
+            threadx_sys::TX_WAIT_ABORTED => Error::WaitAborted,
+            threadx_sys::TX_WAIT_ABORT_ERROR => Error::WaitAbortError,
+            threadx_sys::TX_MUTEX_ERROR => Error::MutexError,
+            threadx_sys::TX_NOT_AVAILABLE => Error::NotAvailable,
+            threadx_sys::TX_NOT_OWNED => Error::NotOwned,
+            threadx_sys::TX_INHERIT_ERROR => Error::InheritError,
+            threadx_sys::TX_NOT_DONE => Error::NotDone,
+            threadx_sys::TX_CEILING_EXCEEDED => Error::CeilingExceeded,
+            threadx_sys::TX_INVALID_CEILING => Error::InvalidCeiling,
+            threadx_sys::TX_FEATURE_NOT_ENABLED => Error::FeatureNotEnabled,
+            other => Error::Unknown(other),
+        };
+        Err(error)
+    }
+}
+
+// End of file
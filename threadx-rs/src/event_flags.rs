@@ -0,0 +1,180 @@
+//! ThreadX event flag groups
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::UnsafeCell,
+    ffi::CStr,
+    marker::PhantomPinned,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use threadx_sys::{TX_EVENT_FLAGS_GROUP, UINT, ULONG};
+
+use crate::{
+    context::{Context, ThreadContext},
+    Error, Wait,
+};
+
+/// How to combine the requested flags when waiting on an [`EventFlags`] group
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GetOption {
+    /// Wait for all of the flags
+    And,
+    /// Wait for all of the flags, and clear them on return
+    AndClear,
+    /// Wait for any of the flags
+    Or,
+    /// Wait for any of the flags, and clear them on return
+    OrClear,
+}
+
+impl GetOption {
+    fn as_raw(self) -> UINT {
+        match self {
+            GetOption::And => threadx_sys::TX_AND,
+            GetOption::AndClear => threadx_sys::TX_AND_CLEAR,
+            GetOption::Or => threadx_sys::TX_OR,
+            GetOption::OrClear => threadx_sys::TX_OR_CLEAR,
+        }
+    }
+}
+
+/// A ThreadX group of 32 event flags
+#[repr(transparent)]
+pub struct EventFlags {
+    inner: UnsafeCell<TX_EVENT_FLAGS_GROUP>,
+    _pin: PhantomPinned,
+}
+
+// Safety: every access to the inner control block goes via a ThreadX API,
+// which handles the locking.
+unsafe impl Sync for EventFlags {}
+
+impl EventFlags {
+    /// Create an empty (all-zero) control block.
+    const fn zeroed() -> EventFlags {
+        EventFlags {
+            // Safety: TX_EVENT_FLAGS_GROUP is plain-old-data, and all zeroes
+            // is a valid value for every field.
+            inner: UnsafeCell::new(unsafe { core::mem::zeroed() }),
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Get a raw pointer to the ThreadX control block
+    pub fn as_ptr(&self) -> *mut TX_EVENT_FLAGS_GROUP {
+        self.inner.get()
+    }
+
+    /// Set the given flags (OR-ing them with the current flags)
+    pub fn set(&self, _ctx: impl Context, flags: u32) -> Result<(), Error> {
+        Error::check(unsafe {
+            threadx_sys::_tx_event_flags_set(self.as_ptr(), flags as ULONG, threadx_sys::TX_OR)
+        })
+    }
+
+    /// Clear the given flags
+    pub fn clear(&self, _ctx: impl Context, flags: u32) -> Result<(), Error> {
+        Error::check(unsafe {
+            threadx_sys::_tx_event_flags_set(self.as_ptr(), !(flags as ULONG), threadx_sys::TX_AND)
+        })
+    }
+
+    /// Wait for some flags to be set.
+    ///
+    /// Returns the flags that were set at the point the wait was satisfied.
+    pub fn get(
+        &self,
+        _ctx: ThreadContext,
+        requested: u32,
+        option: GetOption,
+        wait: Wait,
+    ) -> Result<u32, Error> {
+        self.get_raw(requested, option, wait)
+    }
+
+    /// Get some flags, if they are set.
+    ///
+    /// Returns the flags that were set, or [`Error::NoEvents`].
+    pub fn try_get(
+        &self,
+        _ctx: impl Context,
+        requested: u32,
+        option: GetOption,
+    ) -> Result<u32, Error> {
+        self.get_raw(requested, option, Wait::NONE)
+    }
+
+    fn get_raw(&self, requested: u32, option: GetOption, wait: Wait) -> Result<u32, Error> {
+        let mut actual: ULONG = 0;
+        Error::check(unsafe {
+            threadx_sys::_tx_event_flags_get(
+                self.as_ptr(),
+                requested as ULONG,
+                option.as_raw(),
+                &mut actual,
+                wait.as_raw(),
+            )
+        })?;
+        Ok(actual as u32)
+    }
+}
+
+/// Storage for an event flags group, suitable for placing in a `static`.
+///
+/// Use the [`static_event_flags!`](crate::static_event_flags) macro rather
+/// than using this type directly.
+pub struct StaticEventFlags {
+    flags: EventFlags,
+    created: AtomicBool,
+}
+
+impl StaticEventFlags {
+    /// Reserve space for an event flags group. It ends up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticEventFlags {
+        StaticEventFlags {
+            flags: EventFlags::zeroed(),
+            created: AtomicBool::new(false),
+        }
+    }
+
+    /// Create the ThreadX event flags group, with all flags clear.
+    ///
+    /// Returns [`Error::GroupError`] if the group has already been created.
+    pub fn create(&'static self, name: &'static CStr) -> Result<Pin<&'static EventFlags>, Error> {
+        if self.created.swap(true, Ordering::AcqRel) {
+            return Err(Error::GroupError);
+        }
+        let res = unsafe {
+            threadx_sys::_tx_event_flags_create(self.flags.as_ptr(), crate::name_ptr(name))
+        };
+        if let Err(e) = Error::check(res) {
+            self.created.store(false, Ordering::Release);
+            return Err(e);
+        }
+        Ok(Pin::static_ref(&self.flags))
+    }
+}
+
+/// Reserve an event flags group in `.bss`, and create it.
+///
+/// Evaluates to `Result<Pin<&'static EventFlags>, Error>`.
+///
+/// ```rust,ignore
+/// let events = threadx::static_event_flags!(c"events0")?;
+/// ```
+#[macro_export]
+macro_rules! static_event_flags {
+    ($name:expr) => {{
+        static EVENT_FLAGS: $crate::event_flags::StaticEventFlags =
+            $crate::event_flags::StaticEventFlags::new();
+        EVENT_FLAGS.create($name)
+    }};
+}
+
+// End of file
@@ -0,0 +1,118 @@
+//! Rust wrappers around the ThreadX kernel objects
+//!
+//! Every kernel object (thread, queue, mutex, etc) is a C structure that
+//! ThreadX keeps pointers to, and mutates, for as long as the object exists. We
+//! therefore never hand out `&mut` references to those structures. Instead,
+//! each object lives inside an [`UnsafeCell`](core::cell::UnsafeCell) in a
+//! `static`, is created exactly once, and is then shared as a
+//! `Pin<&'static T>` handle. All operations on a handle go through the ThreadX
+//! API using a raw pointer.
+//!
+//! The `static_*!` macros (e.g. [`static_thread!`], [`static_queue!`]) reserve
+//! the storage for an object (and its stack, or its buffer) in `.bss` and
+//! create the object.
+//!
+//! ```rust,ignore
+//! let thread = threadx::static_thread!(
+//!     16384,
+//!     c"thread0",
+//!     my_thread,
+//!     0x1234_5678,
+//!     threadx::thread::ThreadOptions::new(threadx::thread::Priority::new(1))
+//! )
+//! .expect("creating thread0");
+//! ```
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![no_std]
+
+pub mod block_pool;
+pub mod byte_pool;
+pub mod context;
+pub mod error;
+pub mod event_flags;
+pub mod mutex;
+pub mod panic;
+#[cfg(feature = "performance-info")]
+pub mod performance;
+#[cfg(feature = "execution-profile")]
+pub mod profile;
+pub mod queue;
+pub mod semaphore;
+pub mod snapshot;
+#[cfg(feature = "stack-checking")]
+pub mod stack;
+pub mod sync;
+pub mod thread;
+pub mod time;
+pub mod timer;
+pub mod tls;
+pub mod watchdog;
+pub mod work;
+
+pub use error::Error;
+pub use thread::scope;
+
+/// How long a ThreadX service may block for, in timer ticks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Wait(threadx_sys::ULONG);
+
+impl Wait {
+    /// Return immediately if the service cannot complete.
+    pub const NONE: Wait = Wait(threadx_sys::TX_NO_WAIT);
+    /// Block until the service can complete.
+    pub const FOREVER: Wait = Wait(threadx_sys::TX_WAIT_FOREVER);
+
+    /// Block for up to the given number of timer ticks.
+    pub const fn ticks(ticks: u32) -> Wait {
+        Wait(ticks as threadx_sys::ULONG)
+    }
+
+    /// Get the raw ThreadX wait option
+    pub const fn as_raw(self) -> threadx_sys::ULONG {
+        self.0
+    }
+}
+
+/// Convert a static C string into the name pointer ThreadX expects.
+///
+/// ThreadX requires a non-const pointer to char for the names, which it will
+/// hold on to in the object, so it must have static lifetime. ThreadX never
+/// writes through the pointer, so casting away const is fine.
+pub(crate) fn name_ptr(name: &'static core::ffi::CStr) -> *mut threadx_sys::CHAR {
+    name.as_ptr() as *mut threadx_sys::CHAR
+}
+
+/// Read back a name pointer stored in a ThreadX object.
+///
+/// # Safety
+///
+/// `ptr` must be null, or point to a nul-terminated string which lives
+/// forever. This is true of every name given to ThreadX by this crate.
+pub(crate) unsafe fn name_from_ptr(
+    ptr: *const threadx_sys::CHAR,
+) -> Option<&'static core::ffi::CStr> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(unsafe { core::ffi::CStr::from_ptr(ptr) })
+    }
+}
+
+/// Read back a name pointer stored in a ThreadX object, as a string.
+///
+/// Gives a placeholder if there is no name, or if the name is not valid UTF-8.
+///
+/// # Safety
+///
+/// As for [`name_from_ptr`].
+pub(crate) unsafe fn display_name_from_ptr(ptr: *const threadx_sys::CHAR) -> &'static str {
+    unsafe { name_from_ptr(ptr) }
+        .and_then(|name| name.to_str().ok())
+        .unwrap_or("<unnamed>")
+}
+
+// End of file
@@ -0,0 +1,296 @@
+//! ThreadX mutexes
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::UnsafeCell,
+    ffi::CStr,
+    marker::{PhantomData, PhantomPinned},
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use threadx_sys::TX_MUTEX;
+
+use crate::{context::ThreadContext, Error, Wait};
+
+/// A ThreadX mutex, protecting a value of type `T`.
+pub struct Mutex<T> {
+    inner: UnsafeCell<TX_MUTEX>,
+    data: UnsafeCell<MaybeUninit<T>>,
+    _pin: PhantomPinned,
+}
+
+// Safety: every access to the inner control block goes via a ThreadX API,
+// which handles the locking, and the data is only accessed whilst the mutex
+// is held.
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    /// Create an empty (all-zero) control block, with no data.
+    const fn zeroed() -> Mutex<T> {
+        Mutex {
+            // Safety: TX_MUTEX is plain-old-data, and all zeroes is a valid
+            // value for every field.
+            inner: UnsafeCell::new(unsafe { core::mem::zeroed() }),
+            data: UnsafeCell::new(MaybeUninit::uninit()),
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Get a raw pointer to the ThreadX control block
+    pub fn as_ptr(&self) -> *mut TX_MUTEX {
+        self.inner.get()
+    }
+
+    /// Lock the mutex, and get access to the data within.
+    ///
+    /// ThreadX mutexes are recursive, but handing out two `&mut T` to the same
+    /// data is not allowed, so trying to lock a mutex you already hold returns
+    /// [`Error::WouldDeadlock`].
+    ///
+    /// In debug builds, if `wait` is not [`Wait::NONE`], we also check that
+    /// the thread holding the mutex is not waiting (perhaps via other threads)
+    /// for a mutex that we hold. If it is, we log the threads and mutexes
+    /// involved over defmt and return [`Error::WouldDeadlock`], rather than
+    /// waiting forever.
+    ///
+    /// Mutexes cannot be used from interrupts, even without waiting.
+    pub fn lock(&self, _ctx: ThreadContext, wait: Wait) -> Result<MutexGuard<'_, T>, Error> {
+        let current = unsafe { threadx_sys::_tx_thread_identify() };
+        // Only this thread can make itself the owner, so this check cannot
+        // race with anything that matters.
+        let owner = unsafe { core::ptr::addr_of!((*self.as_ptr()).tx_mutex_owner).read_volatile() };
+        if !current.is_null() && owner == current {
+            return Err(Error::WouldDeadlock);
+        }
+        if wait != Wait::NONE && would_deadlock(self.as_ptr()) {
+            return Err(Error::WouldDeadlock);
+        }
+        Error::check(unsafe { threadx_sys::_tx_mutex_get(self.as_ptr(), wait.as_raw()) })?;
+        // Safety: we have just got the mutex, and did not already hold it
+        Ok(unsafe { MutexGuard::new(self) })
+    }
+}
+
+/// The longest chain of waiting threads we follow when looking for a deadlock
+#[cfg(debug_assertions)]
+const MAX_CHAIN: usize = 16;
+
+/// Check whether the current thread waiting for `mutex` would complete a
+/// cycle of threads, each waiting for a mutex held by the next.
+///
+/// Each thread waits for at most one mutex, so the wait-for graph is a chain
+/// from `mutex` to its owner, to the mutex that owner is suspended on, to
+/// that mutex's owner, and so on. If the chain comes back to us, the cycle is
+/// logged over defmt.
+///
+/// Always returns `false` in release builds.
+#[cfg(debug_assertions)]
+pub(crate) fn would_deadlock(mutex: *mut TX_MUTEX) -> bool {
+    let Some(current) = crate::context::current_thread() else {
+        return false;
+    };
+    let current = current.as_ptr();
+    // Each mutex in the chain, and the thread which owns it
+    let mut chain = [(core::ptr::null_mut(), core::ptr::null_mut()); MAX_CHAIN];
+    // Nothing can take, release or wait for a mutex whilst we look
+    let len = crate::context::without_interrupts(|| {
+        let mut mutex = mutex;
+        for (i, link) in chain.iter_mut().enumerate() {
+            let owner = unsafe { core::ptr::addr_of!((*mutex).tx_mutex_owner).read_volatile() };
+            if owner.is_null() {
+                return None;
+            }
+            *link = (mutex, owner);
+            if owner == current {
+                return Some(i + 1);
+            }
+            let state = unsafe { core::ptr::addr_of!((*owner).tx_thread_state).read_volatile() };
+            if state != threadx_sys::TX_MUTEX_SUSP {
+                return None;
+            }
+            mutex = unsafe {
+                core::ptr::addr_of!((*owner).tx_thread_suspend_control_block).read_volatile()
+            } as *mut TX_MUTEX;
+        }
+        None
+    });
+    let Some(len) = len else {
+        return false;
+    };
+
+    #[cfg(feature = "defmt")]
+    {
+        let thread_name = |thread: *mut threadx_sys::TX_THREAD| unsafe {
+            crate::display_name_from_ptr((*thread).tx_thread_name)
+        };
+        let mutex_name =
+            |mutex: *mut TX_MUTEX| unsafe { crate::display_name_from_ptr((*mutex).tx_mutex_name) };
+        defmt::error!(
+            "Deadlock: thread {=str} would wait forever for mutex {=str}",
+            thread_name(current),
+            mutex_name(mutex)
+        );
+        for (i, &(mutex, owner)) in chain[..len].iter().enumerate() {
+            if i + 1 < len {
+                defmt::error!(
+                    "  mutex {=str} is held by thread {=str}, which is waiting for mutex {=str}",
+                    mutex_name(mutex),
+                    thread_name(owner),
+                    mutex_name(chain[i + 1].0)
+                );
+            } else {
+                defmt::error!(
+                    "  mutex {=str} is held by thread {=str}",
+                    mutex_name(mutex),
+                    thread_name(owner)
+                );
+            }
+        }
+    }
+    #[cfg(not(feature = "defmt"))]
+    let _ = len;
+    true
+}
+
+/// Deadlock detection is compiled out of release builds.
+#[cfg(not(debug_assertions))]
+#[inline(always)]
+pub(crate) fn would_deadlock(_mutex: *mut TX_MUTEX) -> bool {
+    false
+}
+
+/// Proof that we hold a [`Mutex`]. Releases the mutex when dropped.
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+    // ThreadX mutexes must be released by the thread that took them
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<'a, T> MutexGuard<'a, T> {
+    /// Make a guard for a mutex.
+    ///
+    /// # Safety
+    ///
+    /// The current thread must hold the mutex, once, with no other guard.
+    pub(crate) unsafe fn new(mutex: &'a Mutex<T>) -> MutexGuard<'a, T> {
+        MutexGuard {
+            mutex,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Give up the guard without releasing the mutex.
+    pub(crate) fn leak(guard: MutexGuard<'a, T>) -> &'a Mutex<T> {
+        let mutex = guard.mutex;
+        core::mem::forget(guard);
+        mutex
+    }
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: we hold the lock, and the data was initialised on creation
+        unsafe { (*self.mutex.data.get()).assume_init_ref() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // Safety: we hold the lock, and the data was initialised on creation
+        unsafe { (*self.mutex.data.get()).assume_init_mut() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        let _ = unsafe { threadx_sys::_tx_mutex_put(self.mutex.as_ptr()) };
+    }
+}
+
+/// Storage for a mutex and its data, suitable for placing in a `static`.
+///
+/// Use the [`static_mutex!`](crate::static_mutex) macro rather than using this
+/// type directly.
+pub struct StaticMutex<T> {
+    mutex: Mutex<T>,
+    created: AtomicBool,
+}
+
+// Safety: the data is only written once, guarded by `created`.
+unsafe impl<T: Send> Sync for StaticMutex<T> {}
+
+impl<T: Send> StaticMutex<T> {
+    /// Reserve space for a mutex. It ends up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticMutex<T> {
+        StaticMutex {
+            mutex: Mutex::zeroed(),
+            created: AtomicBool::new(false),
+        }
+    }
+
+    /// Create the ThreadX mutex, protecting the given value.
+    ///
+    /// If `inherit` is true, a low priority thread holding the mutex inherits
+    /// the priority of the highest priority thread waiting for it.
+    ///
+    /// Returns [`Error::MutexError`] if the mutex has already been created.
+    pub fn create(
+        &'static self,
+        name: &'static CStr,
+        inherit: bool,
+        value: T,
+    ) -> Result<Pin<&'static Mutex<T>>, Error> {
+        if self.created.swap(true, Ordering::AcqRel) {
+            return Err(Error::MutexError);
+        }
+        // Safety: nobody can see the data until the mutex has been created
+        unsafe {
+            (*self.mutex.data.get()).write(value);
+        }
+        let res = unsafe {
+            threadx_sys::_tx_mutex_create(
+                self.mutex.as_ptr(),
+                crate::name_ptr(name),
+                if inherit {
+                    threadx_sys::TX_INHERIT
+                } else {
+                    threadx_sys::TX_NO_INHERIT
+                },
+            )
+        };
+        if let Err(e) = Error::check(res) {
+            // Safety: we just wrote it, and nobody else can see it
+            unsafe {
+                (*self.mutex.data.get()).assume_init_drop();
+            }
+            self.created.store(false, Ordering::Release);
+            return Err(e);
+        }
+        Ok(Pin::static_ref(&self.mutex))
+    }
+}
+
+/// Reserve a mutex in `.bss`, and create it with priority inheritance enabled.
+///
+/// Evaluates to `Result<Pin<&'static Mutex<T>>, Error>`.
+///
+/// ```rust,ignore
+/// let counter = threadx::static_mutex!(u32, c"counter", 0)?;
+/// ```
+#[macro_export]
+macro_rules! static_mutex {
+    ($t:ty, $name:expr, $value:expr) => {{
+        static MUTEX: $crate::mutex::StaticMutex<$t> = $crate::mutex::StaticMutex::new();
+        MUTEX.create($name, true, $value)
+    }};
+}
+
+// End of file
@@ -0,0 +1,192 @@
+//! Per-thread panic isolation
+//!
+//! Normally a panic anywhere brings down the whole system. Once
+//! [`enable_isolation`] has been called, a panicking thread can instead be
+//! terminated on its own, whilst the other threads keep running. Terminating
+//! a thread does not drop anything on its stack, which is why enabling this is
+//! `unsafe`. Your `#[panic_handler]` must opt in by calling [`isolate`] first:
+//!
+//! ```rust,ignore
+//! #[panic_handler]
+//! fn panic(info: &core::panic::PanicInfo) -> ! {
+//!     // Only returns if the panic could not be isolated
+//!     threadx::panic::isolate(info);
+//!     defmt::error!("PANIC: {:?}", defmt::Debug2Format(info));
+//!     semihosting::process::exit(1);
+//! }
+//! ```
+//!
+//! The supervisor given to [`enable_isolation`] is told about each isolated
+//! panic. It runs on the panicking thread just before that thread is
+//! terminated, so it cannot restart the thread itself. Instead it should hand
+//! the thread (from [`Thread::to_static`] on [`PanicReport::thread`]) to
+//! another thread (e.g. through a [`Queue`](crate::queue::Queue)), which can
+//! call
+//! [`Thread::reset`](crate::thread::Thread::reset) and
+//! [`Thread::resume`](crate::thread::Thread::resume). The panicking thread
+//! cannot be preempted whilst the supervisor runs, so any thread the
+//! supervisor wakes up will find it already terminated.
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    fmt::Write,
+    panic::{Location, PanicInfo},
+    pin::Pin,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use crate::{context::ThreadContext, thread::Thread};
+
+/// The most bytes of a panic message we keep
+const MESSAGE_LEN: usize = 128;
+
+/// Called when a thread panics, on that thread, before it is terminated
+pub type Supervisor = fn(ThreadContext, &PanicReport<'_>);
+
+/// The supervisor, or null if panic isolation is not enabled
+static SUPERVISOR: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Details of a panic in a thread
+#[derive(Debug, Copy, Clone)]
+pub struct PanicReport<'a> {
+    /// The thread which panicked.
+    ///
+    /// Only borrowed, as a [spawned](crate::thread::spawn) thread's control
+    /// block is freed when it is joined.
+    pub thread: Pin<&'a Thread>,
+    /// The panic message, truncated to fit our buffer
+    pub message: &'a str,
+    /// Where the panic happened, if known
+    pub location: Option<&'a Location<'a>>,
+}
+
+/// Enable panic isolation, and set the supervisor to tell about each panic.
+///
+/// # Safety
+///
+/// A thread which panics is terminated, so the requirements of
+/// [`Thread::terminate`] apply to every thread which might panic.
+pub unsafe fn enable_isolation(supervisor: Supervisor) {
+    SUPERVISOR.store(supervisor as *mut (), Ordering::Release);
+}
+
+/// Try to isolate a panic to the thread it happened in.
+///
+/// If isolation is enabled and we are in a thread, this reports the panic,
+/// calls the supervisor and terminates the current thread, so it does not
+/// return.
+///
+/// Returns if the panic cannot be isolated, and the panic handler must deal
+/// with it in the usual way. That happens if isolation is not enabled, if the
+/// panic happened during initialisation or in an interrupt, or if the thread
+/// panicked again whilst its first panic was being handled.
+pub fn isolate(info: &PanicInfo) {
+    let supervisor = SUPERVISOR.load(Ordering::Acquire);
+    if supervisor.is_null() {
+        return;
+    }
+    // Safety: only `enable_isolation` stores to SUPERVISOR, and it stores a
+    // `Supervisor`.
+    let supervisor: Supervisor = unsafe { core::mem::transmute(supervisor) };
+    let Some(thread) = crate::context::current_thread() else {
+        return;
+    };
+    // Safety: the thread is running, so it cannot be deleted until it has
+    // been terminated, after which we do not return
+    let thread = unsafe { Pin::new_unchecked(Thread::from_ptr(thread.as_ptr())) };
+    let panicking = unsafe { core::ptr::addr_of_mut!((*thread.as_ptr()).tx_thread_rust_panicking) };
+    // Only this thread can change its own flag, until it has been terminated
+    if unsafe { panicking.read_volatile() } != 0 {
+        return;
+    }
+    unsafe { panicking.write_volatile(1) };
+
+    let mut buffer = MessageBuffer {
+        bytes: [0; MESSAGE_LEN],
+        len: 0,
+    };
+    let _ = write!(buffer, "{}", info.message());
+    let report = PanicReport {
+        thread,
+        message: buffer.as_str(),
+        location: info.location(),
+    };
+
+    #[cfg(feature = "defmt")]
+    {
+        let (file, line) = report
+            .location
+            .map(|l| (l.file(), l.line()))
+            .unwrap_or(("<unknown>", 0));
+        defmt::error!(
+            "Thread {=str} panicked at {=str}:{=u32}: {=str}",
+            thread.display_name(),
+            file,
+            line,
+            report.message
+        );
+    }
+
+    // Our scoped threads may be borrowing from our stack, so they must
+    // finish before we are terminated.
+    // Safety: `current_thread` checked that we are in a thread, and we never
+    // return to the scopes.
+    unsafe {
+        crate::thread::join_scopes(ThreadContext::new_unchecked(), thread.as_ptr());
+    }
+
+    // Nothing may preempt us until we have terminated, so that any thread
+    // the supervisor wakes up finds this thread already terminated.
+    // `tx_thread_reset` would keep this threshold, so `Thread::reset` puts
+    // back the one we had, which we keep alongside our panicking flag.
+    let mut old_threshold = 0;
+    let _ = unsafe {
+        threadx_sys::_tx_thread_preemption_change(thread.as_ptr(), 0, &mut old_threshold)
+    };
+    unsafe {
+        core::ptr::addr_of_mut!((*thread.as_ptr()).tx_thread_rust_threshold)
+            .write_volatile(old_threshold);
+    }
+
+    // Safety: `current_thread` checked that we are in a thread
+    supervisor(unsafe { ThreadContext::new_unchecked() }, &report);
+
+    // This does not return, unless something is very wrong.
+    // Safety: whoever enabled isolation promised this is fine, and we have
+    // waited for anything borrowing from our stack in a scope.
+    let _ = unsafe { thread.terminate() };
+}
+
+/// Somewhere to format a panic message into, without allocating
+struct MessageBuffer {
+    bytes: [u8; MESSAGE_LEN],
+    len: usize,
+}
+
+impl MessageBuffer {
+    fn as_str(&self) -> &str {
+        // Safety: `write_str` only ever copies whole UTF-8 characters
+        unsafe { core::str::from_utf8_unchecked(&self.bytes[..self.len]) }
+    }
+}
+
+impl Write for MessageBuffer {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let space = MESSAGE_LEN - self.len;
+        let mut take = s.len().min(space);
+        while !s.is_char_boundary(take) {
+            take -= 1;
+        }
+        self.bytes[self.len..self.len + take].copy_from_slice(&s.as_bytes()[..take]);
+        self.len += take;
+        if take < s.len() {
+            Err(core::fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// End of file
@@ -0,0 +1,470 @@
+//! Performance counters
+//!
+//! With the `performance-info` feature, ThreadX is built with the
+//! `TX_*_ENABLE_PERFORMANCE_INFO` options, and counts how often each kernel
+//! object is used. Each object type gains a `performance_info` method, and
+//! the functions in this module report the totals for each type across the
+//! whole system.
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use threadx_sys::ULONG;
+
+use crate::{
+    block_pool::BlockPool, byte_pool::BytePool, event_flags::EventFlags, mutex::Mutex,
+    queue::Queue, semaphore::Semaphore, snapshot::ObjectId, thread::Thread, timer::Timer, Error,
+};
+
+/// Counters for one thread
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ThreadPerformance {
+    /// Times the thread has been resumed
+    pub resumptions: ULONG,
+    /// Times the thread has been suspended
+    pub suspensions: ULONG,
+    /// Times the thread was preempted because it made a higher priority
+    /// thread ready
+    pub solicited_preemptions: ULONG,
+    /// Times the thread was preempted by an interrupt making a higher
+    /// priority thread ready
+    pub interrupt_preemptions: ULONG,
+    /// Times the thread has suffered a priority inversion
+    pub priority_inversions: ULONG,
+    /// Times the thread's time slice has expired
+    pub time_slices: ULONG,
+    /// Times the thread has relinquished the processor
+    pub relinquishes: ULONG,
+    /// Times a suspension of the thread has timed out
+    pub timeouts: ULONG,
+    /// Times a suspension of the thread has been aborted
+    pub wait_aborts: ULONG,
+    /// The thread which last preempted this one, if any
+    pub last_preempted_by: Option<ObjectId>,
+}
+
+/// Counters for all threads
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SystemThreadPerformance {
+    /// Thread resumptions
+    pub resumptions: ULONG,
+    /// Thread suspensions
+    pub suspensions: ULONG,
+    /// Preemptions caused by a thread making a higher priority thread ready
+    pub solicited_preemptions: ULONG,
+    /// Preemptions caused by an interrupt making a higher priority thread
+    /// ready
+    pub interrupt_preemptions: ULONG,
+    /// Priority inversions
+    pub priority_inversions: ULONG,
+    /// Time slice expiries
+    pub time_slices: ULONG,
+    /// Times a thread has relinquished the processor
+    pub relinquishes: ULONG,
+    /// Suspension timeouts
+    pub timeouts: ULONG,
+    /// Suspension aborts
+    pub wait_aborts: ULONG,
+    /// Times an interrupt returned to a thread
+    pub non_idle_returns: ULONG,
+    /// Times an interrupt returned to the idle system
+    pub idle_returns: ULONG,
+}
+
+/// Counters for one queue, or for all queues
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct QueuePerformance {
+    /// Messages sent
+    pub messages_sent: ULONG,
+    /// Messages received
+    pub messages_received: ULONG,
+    /// Times a receiver suspended because the queue was empty
+    pub empty_suspensions: ULONG,
+    /// Times a sender suspended because the queue was full
+    pub full_suspensions: ULONG,
+    /// Times a send failed because the queue was full
+    pub full_errors: ULONG,
+    /// Suspension timeouts
+    pub timeouts: ULONG,
+}
+
+/// Counters for one semaphore, or for all semaphores
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SemaphorePerformance {
+    /// Puts
+    pub puts: ULONG,
+    /// Gets
+    pub gets: ULONG,
+    /// Times a getter suspended
+    pub suspensions: ULONG,
+    /// Suspension timeouts
+    pub timeouts: ULONG,
+}
+
+/// Counters for one mutex, or for all mutexes
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MutexPerformance {
+    /// Puts
+    pub puts: ULONG,
+    /// Gets
+    pub gets: ULONG,
+    /// Times a getter suspended
+    pub suspensions: ULONG,
+    /// Suspension timeouts
+    pub timeouts: ULONG,
+    /// Priority inversions
+    pub inversions: ULONG,
+    /// Times the owner inherited a higher priority
+    pub inheritances: ULONG,
+}
+
+/// Counters for one event flags group, or for all groups
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EventFlagsPerformance {
+    /// Sets
+    pub sets: ULONG,
+    /// Gets
+    pub gets: ULONG,
+    /// Times a getter suspended
+    pub suspensions: ULONG,
+    /// Suspension timeouts
+    pub timeouts: ULONG,
+}
+
+/// Counters for one byte pool, or for all byte pools
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BytePoolPerformance {
+    /// Allocations
+    pub allocates: ULONG,
+    /// Releases
+    pub releases: ULONG,
+    /// Fragments searched during allocation
+    pub fragments_searched: ULONG,
+    /// Fragments merged during allocation
+    pub merges: ULONG,
+    /// Fragments split during allocation
+    pub splits: ULONG,
+    /// Times an allocator suspended
+    pub suspensions: ULONG,
+    /// Suspension timeouts
+    pub timeouts: ULONG,
+}
+
+/// Counters for one block pool, or for all block pools
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BlockPoolPerformance {
+    /// Allocations
+    pub allocates: ULONG,
+    /// Releases
+    pub releases: ULONG,
+    /// Times an allocator suspended
+    pub suspensions: ULONG,
+    /// Suspension timeouts
+    pub timeouts: ULONG,
+}
+
+/// Counters for one timer, or for all timers
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimerPerformance {
+    /// Activations
+    pub activates: ULONG,
+    /// Automatic reactivations of periodic timers
+    pub reactivates: ULONG,
+    /// Deactivations
+    pub deactivates: ULONG,
+    /// Expiries
+    pub expirations: ULONG,
+    /// Expiry adjustments, made when a large expiry is split up
+    pub expiration_adjusts: ULONG,
+}
+
+impl Thread {
+    /// Get the performance counters for this thread
+    pub fn performance_info(&self) -> Result<ThreadPerformance, Error> {
+        let mut p = ThreadPerformance::default();
+        let mut last_preempted_by = core::ptr::null_mut();
+        Error::check(unsafe {
+            threadx_sys::_tx_thread_performance_info_get(
+                self.as_ptr(),
+                &mut p.resumptions,
+                &mut p.suspensions,
+                &mut p.solicited_preemptions,
+                &mut p.interrupt_preemptions,
+                &mut p.priority_inversions,
+                &mut p.time_slices,
+                &mut p.relinquishes,
+                &mut p.timeouts,
+                &mut p.wait_aborts,
+                &mut last_preempted_by,
+            )
+        })?;
+        p.last_preempted_by =
+            (!last_preempted_by.is_null()).then(|| ObjectId::of(last_preempted_by));
+        Ok(p)
+    }
+}
+
+impl<T: Copy + Send> Queue<T> {
+    /// Get the performance counters for this queue
+    pub fn performance_info(&self) -> Result<QueuePerformance, Error> {
+        let mut p = QueuePerformance::default();
+        Error::check(unsafe {
+            threadx_sys::_tx_queue_performance_info_get(
+                self.as_ptr(),
+                &mut p.messages_sent,
+                &mut p.messages_received,
+                &mut p.empty_suspensions,
+                &mut p.full_suspensions,
+                &mut p.full_errors,
+                &mut p.timeouts,
+            )
+        })?;
+        Ok(p)
+    }
+}
+
+impl Semaphore {
+    /// Get the performance counters for this semaphore
+    pub fn performance_info(&self) -> Result<SemaphorePerformance, Error> {
+        let mut p = SemaphorePerformance::default();
+        Error::check(unsafe {
+            threadx_sys::_tx_semaphore_performance_info_get(
+                self.as_ptr(),
+                &mut p.puts,
+                &mut p.gets,
+                &mut p.suspensions,
+                &mut p.timeouts,
+            )
+        })?;
+        Ok(p)
+    }
+}
+
+impl<T> Mutex<T> {
+    /// Get the performance counters for this mutex
+    pub fn performance_info(&self) -> Result<MutexPerformance, Error> {
+        let mut p = MutexPerformance::default();
+        Error::check(unsafe {
+            threadx_sys::_tx_mutex_performance_info_get(
+                self.as_ptr(),
+                &mut p.puts,
+                &mut p.gets,
+                &mut p.suspensions,
+                &mut p.timeouts,
+                &mut p.inversions,
+                &mut p.inheritances,
+            )
+        })?;
+        Ok(p)
+    }
+}
+
+impl EventFlags {
+    /// Get the performance counters for this event flags group
+    pub fn performance_info(&self) -> Result<EventFlagsPerformance, Error> {
+        let mut p = EventFlagsPerformance::default();
+        Error::check(unsafe {
+            threadx_sys::_tx_event_flags_performance_info_get(
+                self.as_ptr(),
+                &mut p.sets,
+                &mut p.gets,
+                &mut p.suspensions,
+                &mut p.timeouts,
+            )
+        })?;
+        Ok(p)
+    }
+}
+
+impl BytePool {
+    /// Get the performance counters for this byte pool
+    pub fn performance_info(&self) -> Result<BytePoolPerformance, Error> {
+        let mut p = BytePoolPerformance::default();
+        Error::check(unsafe {
+            threadx_sys::_tx_byte_pool_performance_info_get(
+                self.as_ptr(),
+                &mut p.allocates,
+                &mut p.releases,
+                &mut p.fragments_searched,
+                &mut p.merges,
+                &mut p.splits,
+                &mut p.suspensions,
+                &mut p.timeouts,
+            )
+        })?;
+        Ok(p)
+    }
+}
+
+impl BlockPool {
+    /// Get the performance counters for this block pool
+    pub fn performance_info(&self) -> Result<BlockPoolPerformance, Error> {
+        let mut p = BlockPoolPerformance::default();
+        Error::check(unsafe {
+            threadx_sys::_tx_block_pool_performance_info_get(
+                self.as_ptr(),
+                &mut p.allocates,
+                &mut p.releases,
+                &mut p.suspensions,
+                &mut p.timeouts,
+            )
+        })?;
+        Ok(p)
+    }
+}
+
+impl Timer {
+    /// Get the performance counters for this timer
+    pub fn performance_info(&self) -> Result<TimerPerformance, Error> {
+        let mut p = TimerPerformance::default();
+        Error::check(unsafe {
+            threadx_sys::_tx_timer_performance_info_get(
+                self.as_ptr(),
+                &mut p.activates,
+                &mut p.reactivates,
+                &mut p.deactivates,
+                &mut p.expirations,
+                &mut p.expiration_adjusts,
+            )
+        })?;
+        Ok(p)
+    }
+}
+
+/// Get the performance counters for all threads
+pub fn threads() -> Result<SystemThreadPerformance, Error> {
+    let mut p = SystemThreadPerformance::default();
+    Error::check(unsafe {
+        threadx_sys::_tx_thread_performance_system_info_get(
+            &mut p.resumptions,
+            &mut p.suspensions,
+            &mut p.solicited_preemptions,
+            &mut p.interrupt_preemptions,
+            &mut p.priority_inversions,
+            &mut p.time_slices,
+            &mut p.relinquishes,
+            &mut p.timeouts,
+            &mut p.wait_aborts,
+            &mut p.non_idle_returns,
+            &mut p.idle_returns,
+        )
+    })?;
+    Ok(p)
+}
+
+/// Get the performance counters for all queues
+pub fn queues() -> Result<QueuePerformance, Error> {
+    let mut p = QueuePerformance::default();
+    Error::check(unsafe {
+        threadx_sys::_tx_queue_performance_system_info_get(
+            &mut p.messages_sent,
+            &mut p.messages_received,
+            &mut p.empty_suspensions,
+            &mut p.full_suspensions,
+            &mut p.full_errors,
+            &mut p.timeouts,
+        )
+    })?;
+    Ok(p)
+}
+
+/// Get the performance counters for all semaphores
+pub fn semaphores() -> Result<SemaphorePerformance, Error> {
+    let mut p = SemaphorePerformance::default();
+    Error::check(unsafe {
+        threadx_sys::_tx_semaphore_performance_system_info_get(
+            &mut p.puts,
+            &mut p.gets,
+            &mut p.suspensions,
+            &mut p.timeouts,
+        )
+    })?;
+    Ok(p)
+}
+
+/// Get the performance counters for all mutexes
+pub fn mutexes() -> Result<MutexPerformance, Error> {
+    let mut p = MutexPerformance::default();
+    Error::check(unsafe {
+        threadx_sys::_tx_mutex_performance_system_info_get(
+            &mut p.puts,
+            &mut p.gets,
+            &mut p.suspensions,
+            &mut p.timeouts,
+            &mut p.inversions,
+            &mut p.inheritances,
+        )
+    })?;
+    Ok(p)
+}
+
+/// Get the performance counters for all event flags groups
+pub fn event_flags() -> Result<EventFlagsPerformance, Error> {
+    let mut p = EventFlagsPerformance::default();
+    Error::check(unsafe {
+        threadx_sys::_tx_event_flags_performance_system_info_get(
+            &mut p.sets,
+            &mut p.gets,
+            &mut p.suspensions,
+            &mut p.timeouts,
+        )
+    })?;
+    Ok(p)
+}
+
+/// Get the performance counters for all byte pools
+pub fn byte_pools() -> Result<BytePoolPerformance, Error> {
+    let mut p = BytePoolPerformance::default();
+    Error::check(unsafe {
+        threadx_sys::_tx_byte_pool_performance_system_info_get(
+            &mut p.allocates,
+            &mut p.releases,
+            &mut p.fragments_searched,
+            &mut p.merges,
+            &mut p.splits,
+            &mut p.suspensions,
+            &mut p.timeouts,
+        )
+    })?;
+    Ok(p)
+}
+
+/// Get the performance counters for all block pools
+pub fn block_pools() -> Result<BlockPoolPerformance, Error> {
+    let mut p = BlockPoolPerformance::default();
+    Error::check(unsafe {
+        threadx_sys::_tx_block_pool_performance_system_info_get(
+            &mut p.allocates,
+            &mut p.releases,
+            &mut p.suspensions,
+            &mut p.timeouts,
+        )
+    })?;
+    Ok(p)
+}
+
+/// Get the performance counters for all timers
+pub fn timers() -> Result<TimerPerformance, Error> {
+    let mut p = TimerPerformance::default();
+    Error::check(unsafe {
+        threadx_sys::_tx_timer_performance_system_info_get(
+            &mut p.activates,
+            &mut p.reactivates,
+            &mut p.deactivates,
+            &mut p.expirations,
+            &mut p.expiration_adjusts,
+        )
+    })?;
+    Ok(p)
+}
+
+// End of file
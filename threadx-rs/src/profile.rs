@@ -0,0 +1,116 @@
+//! CPU load measurement with the ThreadX execution profile kit
+//!
+//! With the `execution-profile` feature, ThreadX is built with
+//! `TX_EXECUTION_PROFILE_ENABLE`. The port then reads a free-running counter
+//! (`TX_EXECUTION_TIME_SOURCE`) every time it switches between threads,
+//! interrupts and idle, and adds up how long was spent in each. Times are
+//! reported in ticks of that counter - on Cortex-M this is the DWT cycle
+//! counter, which must have been enabled.
+//!
+//! Some ports (e.g. Cortex-M) cannot see interrupts which do not go through
+//! the ThreadX context save and restore, so wrap the body of any interrupt
+//! handler you want counted in [`isr`].
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use threadx_sys::{TX_THREAD, UINT};
+
+use crate::{context::IsrContext, thread::Thread, Error};
+
+/// The `EXECUTION_TIME` type, as `threadx-sys` sets `TX_EXECUTION_64BIT_TIME`
+type ExecutionTime = u64;
+
+extern "C" {
+    fn _tx_execution_isr_enter();
+    fn _tx_execution_isr_exit();
+    fn _tx_execution_thread_time_get(
+        thread_ptr: *mut TX_THREAD,
+        total_time: *mut ExecutionTime,
+    ) -> UINT;
+    fn _tx_execution_thread_total_time_get(total_time: *mut ExecutionTime) -> UINT;
+    fn _tx_execution_isr_time_get(total_time: *mut ExecutionTime) -> UINT;
+    fn _tx_execution_idle_time_get(total_time: *mut ExecutionTime) -> UINT;
+    fn _tx_execution_thread_time_reset(thread_ptr: *mut TX_THREAD) -> UINT;
+    fn _tx_execution_thread_total_time_reset() -> UINT;
+    fn _tx_execution_isr_time_reset() -> UINT;
+    fn _tx_execution_idle_time_reset() -> UINT;
+}
+
+/// Where the CPU time has gone, in ticks of the execution time source
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CpuTime {
+    /// Time spent in all threads
+    pub threads: u64,
+    /// Time spent in interrupt handlers
+    pub isrs: u64,
+    /// Time spent with nothing to do
+    pub idle: u64,
+}
+
+impl CpuTime {
+    /// All the time we have measured
+    pub const fn total(&self) -> u64 {
+        self.threads + self.isrs + self.idle
+    }
+
+    /// The share of the measured time that the given amount is, in parts
+    /// per thousand
+    pub const fn per_mille(&self, time: u64) -> u64 {
+        match self.total() {
+            0 => 0,
+            total => time.saturating_mul(1000) / total,
+        }
+    }
+}
+
+impl Thread {
+    /// Get the time this thread has spent running, in ticks of the execution
+    /// time source
+    pub fn cpu_time(&self) -> Result<u64, Error> {
+        let mut time = 0;
+        Error::check(unsafe { _tx_execution_thread_time_get(self.as_ptr(), &mut time) })?;
+        Ok(time)
+    }
+
+    /// Set the time this thread has spent running back to zero
+    pub fn reset_cpu_time(&self) -> Result<(), Error> {
+        Error::check(unsafe { _tx_execution_thread_time_reset(self.as_ptr()) })
+    }
+}
+
+/// Get the time spent in threads, interrupts and idle
+pub fn cpu_time() -> Result<CpuTime, Error> {
+    let mut result = CpuTime::default();
+    Error::check(unsafe { _tx_execution_thread_total_time_get(&mut result.threads) })?;
+    Error::check(unsafe { _tx_execution_isr_time_get(&mut result.isrs) })?;
+    Error::check(unsafe { _tx_execution_idle_time_get(&mut result.idle) })?;
+    Ok(result)
+}
+
+/// Set the total times for threads, interrupts and idle back to zero.
+///
+/// Use [`Thread::reset_cpu_time`] to reset the time for a single thread.
+pub fn reset() -> Result<(), Error> {
+    Error::check(unsafe { _tx_execution_thread_total_time_reset() })?;
+    Error::check(unsafe { _tx_execution_isr_time_reset() })?;
+    Error::check(unsafe { _tx_execution_idle_time_reset() })
+}
+
+/// Count the time spent in `f` as interrupt time.
+///
+/// Call this from interrupt handlers on ports which do not tell the execution
+/// profile kit about interrupts themselves. It takes an [`IsrContext`], as
+/// calling it from a thread would count that thread's time as interrupt time.
+pub fn isr<F, R>(_ctx: IsrContext, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    unsafe { _tx_execution_isr_enter() };
+    let result = f();
+    unsafe { _tx_execution_isr_exit() };
+    result
+}
+
+// End of file
@@ -0,0 +1,225 @@
+//! ThreadX message queues
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::UnsafeCell,
+    ffi::CStr,
+    marker::{PhantomData, PhantomPinned},
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use threadx_sys::{TX_QUEUE, UINT, ULONG};
+
+use crate::{
+    context::{Context, ThreadContext},
+    Error, Wait,
+};
+
+/// The largest message ThreadX supports, in words
+const MAX_MESSAGE_WORDS: usize = 16;
+
+/// Work out how many ThreadX words a message of type `T` occupies.
+///
+/// ThreadX supports messages of 1, 2, 4, 8 or 16 words, so we round up.
+pub const fn message_words<T>() -> usize {
+    let words = core::mem::size_of::<T>().div_ceil(core::mem::size_of::<ULONG>());
+    match words {
+        0 | 1 => 1,
+        2 => 2,
+        3 | 4 => 4,
+        5..=8 => 8,
+        9..=MAX_MESSAGE_WORDS => 16,
+        _ => panic!("Message type is too large for a ThreadX queue"),
+    }
+}
+
+/// A ThreadX message queue, carrying values of type `T`.
+///
+/// ThreadX copies messages in and out of the queue word by word, so `T` must
+/// be [`Copy`].
+#[repr(transparent)]
+pub struct Queue<T> {
+    inner: UnsafeCell<TX_QUEUE>,
+    _marker: PhantomData<T>,
+    _pin: PhantomPinned,
+}
+
+// Safety: every access to the inner control block goes via a ThreadX API,
+// which handles the locking. Values of `T` are sent between threads.
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+impl<T: Copy + Send> Queue<T> {
+    /// Create an empty (all-zero) control block.
+    const fn zeroed() -> Queue<T> {
+        Queue {
+            // Safety: TX_QUEUE is plain-old-data, and all zeroes is a valid
+            // value for every field.
+            inner: UnsafeCell::new(unsafe { core::mem::zeroed() }),
+            _marker: PhantomData,
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Get a raw pointer to the ThreadX control block
+    pub fn as_ptr(&self) -> *mut TX_QUEUE {
+        self.inner.get()
+    }
+
+    /// Send a message to the back of the queue, waiting for space if
+    /// required.
+    pub fn send(&self, _ctx: ThreadContext, message: T, wait: Wait) -> Result<(), Error> {
+        self.send_raw(message, wait)
+    }
+
+    /// Send a message to the back of the queue, if there is space.
+    pub fn try_send(&self, _ctx: impl Context, message: T) -> Result<(), Error> {
+        self.send_raw(message, Wait::NONE)
+    }
+
+    fn send_raw(&self, message: T, wait: Wait) -> Result<(), Error> {
+        let mut buffer = [0 as ULONG; MAX_MESSAGE_WORDS];
+        // Safety: the buffer is larger than any permitted `T`
+        unsafe {
+            core::ptr::write_unaligned(buffer.as_mut_ptr() as *mut T, message);
+        }
+        Error::check(unsafe {
+            threadx_sys::_tx_queue_send(self.as_ptr(), buffer.as_mut_ptr() as *mut _, wait.as_raw())
+        })
+    }
+
+    /// Receive a message from the front of the queue, waiting for one if
+    /// required.
+    pub fn receive(&self, _ctx: ThreadContext, wait: Wait) -> Result<T, Error> {
+        self.receive_raw(wait)
+    }
+
+    /// Receive a message from the front of the queue, if there is one.
+    pub fn try_receive(&self, _ctx: impl Context) -> Result<T, Error> {
+        self.receive_raw(Wait::NONE)
+    }
+
+    fn receive_raw(&self, wait: Wait) -> Result<T, Error> {
+        let mut buffer = [0 as ULONG; MAX_MESSAGE_WORDS];
+        Error::check(unsafe {
+            threadx_sys::_tx_queue_receive(
+                self.as_ptr(),
+                buffer.as_mut_ptr() as *mut _,
+                wait.as_raw(),
+            )
+        })?;
+        // Safety: the only way to put values into the queue is `send`, which
+        // writes a valid `T` at the start of the message.
+        Ok(unsafe { core::ptr::read_unaligned(buffer.as_ptr() as *const T) })
+    }
+}
+
+/// Storage for a queue and its message buffer, suitable for placing in a
+/// `static`.
+///
+/// `WORDS` is the size of the buffer in ThreadX words. Use the
+/// [`static_queue!`](crate::static_queue) macro, which will work it out for
+/// you, rather than using this type directly.
+pub struct StaticQueue<T, const WORDS: usize> {
+    queue: Queue<T>,
+    buffer: UnsafeCell<[ULONG; WORDS]>,
+    created: AtomicBool,
+    ready: AtomicBool,
+}
+
+// Safety: the buffer is only handed to ThreadX, once, guarded by `created`.
+unsafe impl<T: Send, const WORDS: usize> Sync for StaticQueue<T, WORDS> {}
+
+impl<T: Copy + Send, const WORDS: usize> StaticQueue<T, WORDS> {
+    /// Reserve space for a queue. It ends up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticQueue<T, WORDS> {
+        StaticQueue {
+            queue: Queue::zeroed(),
+            buffer: UnsafeCell::new([0; WORDS]),
+            created: AtomicBool::new(false),
+            ready: AtomicBool::new(false),
+        }
+    }
+
+    /// Get the queue, if it has been created.
+    ///
+    /// Useful when the queue lives in a `static` of its own, and the code
+    /// using it cannot be given the handle returned by
+    /// [`create`](Self::create).
+    pub fn get(&'static self) -> Option<Pin<&'static Queue<T>>> {
+        if self.ready.load(Ordering::Acquire) {
+            Some(Pin::static_ref(&self.queue))
+        } else {
+            None
+        }
+    }
+
+    /// Create the ThreadX queue.
+    ///
+    /// Returns [`Error::QueueError`] if the queue has already been created.
+    pub fn create(&'static self, name: &'static CStr) -> Result<Pin<&'static Queue<T>>, Error> {
+        if self.created.swap(true, Ordering::AcqRel) {
+            return Err(Error::QueueError);
+        }
+        let res = unsafe {
+            threadx_sys::_tx_queue_create(
+                self.queue.as_ptr(),
+                crate::name_ptr(name),
+                message_words::<T>() as UINT,
+                self.buffer.get() as *mut _,
+                core::mem::size_of::<[ULONG; WORDS]>() as ULONG,
+            )
+        };
+        if let Err(e) = Error::check(res) {
+            self.created.store(false, Ordering::Release);
+            return Err(e);
+        }
+        self.ready.store(true, Ordering::Release);
+        Ok(Pin::static_ref(&self.queue))
+    }
+
+    /// Delete the ThreadX queue, so that it can be created again.
+    ///
+    /// Any threads waiting on the queue are resumed with
+    /// [`Error::Deleted`].
+    ///
+    /// # Safety
+    ///
+    /// The handle returned by [`create`](Self::create) or [`get`](Self::get)
+    /// must not be used again after the queue has been deleted.
+    pub unsafe fn delete(&'static self) -> Result<(), Error> {
+        if !self.ready.swap(false, Ordering::AcqRel) {
+            return Err(Error::QueueError);
+        }
+        let res = unsafe { threadx_sys::_tx_queue_delete(self.queue.as_ptr()) };
+        if let Err(e) = Error::check(res) {
+            self.ready.store(true, Ordering::Release);
+            return Err(e);
+        }
+        self.created.store(false, Ordering::Release);
+        Ok(())
+    }
+}
+
+/// Reserve a queue and its buffer in `.bss`, and create it.
+///
+/// Evaluates to `Result<Pin<&'static Queue<T>>, Error>`.
+///
+/// ```rust,ignore
+/// let queue = threadx::static_queue!(u32, 16, c"queue0")?;
+/// ```
+#[macro_export]
+macro_rules! static_queue {
+    ($t:ty, $depth:expr, $name:expr) => {{
+        static QUEUE: $crate::queue::StaticQueue<
+            $t,
+            { $depth * $crate::queue::message_words::<$t>() },
+        > = $crate::queue::StaticQueue::new();
+        QUEUE.create($name)
+    }};
+}
+
+// End of file
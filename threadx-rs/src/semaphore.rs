@@ -0,0 +1,128 @@
+//! ThreadX counting semaphores
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::UnsafeCell,
+    ffi::CStr,
+    marker::PhantomPinned,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use threadx_sys::{TX_SEMAPHORE, ULONG};
+
+use crate::{
+    context::{Context, ThreadContext},
+    Error, Wait,
+};
+
+/// A ThreadX counting semaphore
+#[repr(transparent)]
+pub struct Semaphore {
+    inner: UnsafeCell<TX_SEMAPHORE>,
+    _pin: PhantomPinned,
+}
+
+// Safety: every access to the inner control block goes via a ThreadX API,
+// which handles the locking.
+unsafe impl Sync for Semaphore {}
+
+impl Semaphore {
+    /// Create an empty (all-zero) control block.
+    const fn zeroed() -> Semaphore {
+        Semaphore {
+            // Safety: TX_SEMAPHORE is plain-old-data, and all zeroes is a
+            // valid value for every field.
+            inner: UnsafeCell::new(unsafe { core::mem::zeroed() }),
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Get a raw pointer to the ThreadX control block
+    pub fn as_ptr(&self) -> *mut TX_SEMAPHORE {
+        self.inner.get()
+    }
+
+    /// Decrement the count, waiting for it to become non-zero if required.
+    pub fn get(&self, _ctx: ThreadContext, wait: Wait) -> Result<(), Error> {
+        Error::check(unsafe { threadx_sys::_tx_semaphore_get(self.as_ptr(), wait.as_raw()) })
+    }
+
+    /// Decrement the count, if it is non-zero.
+    pub fn try_get(&self, _ctx: impl Context) -> Result<(), Error> {
+        Error::check(unsafe {
+            threadx_sys::_tx_semaphore_get(self.as_ptr(), threadx_sys::TX_NO_WAIT)
+        })
+    }
+
+    /// Increment the count, waking up a waiting thread if there is one.
+    pub fn put(&self, _ctx: impl Context) -> Result<(), Error> {
+        Error::check(unsafe { threadx_sys::_tx_semaphore_put(self.as_ptr()) })
+    }
+}
+
+/// Storage for a semaphore, suitable for placing in a `static`.
+///
+/// Use the [`static_semaphore!`](crate::static_semaphore) macro rather than
+/// using this type directly.
+pub struct StaticSemaphore {
+    semaphore: Semaphore,
+    created: AtomicBool,
+}
+
+impl StaticSemaphore {
+    /// Reserve space for a semaphore. It ends up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticSemaphore {
+        StaticSemaphore {
+            semaphore: Semaphore::zeroed(),
+            created: AtomicBool::new(false),
+        }
+    }
+
+    /// Create the ThreadX semaphore with the given initial count.
+    ///
+    /// Returns [`Error::SemaphoreError`] if the semaphore has already been
+    /// created.
+    pub fn create(
+        &'static self,
+        name: &'static CStr,
+        initial_count: u32,
+    ) -> Result<Pin<&'static Semaphore>, Error> {
+        if self.created.swap(true, Ordering::AcqRel) {
+            return Err(Error::SemaphoreError);
+        }
+        let res = unsafe {
+            threadx_sys::_tx_semaphore_create(
+                self.semaphore.as_ptr(),
+                crate::name_ptr(name),
+                initial_count as ULONG,
+            )
+        };
+        if let Err(e) = Error::check(res) {
+            self.created.store(false, Ordering::Release);
+            return Err(e);
+        }
+        Ok(Pin::static_ref(&self.semaphore))
+    }
+}
+
+/// Reserve a semaphore in `.bss`, and create it.
+///
+/// Evaluates to `Result<Pin<&'static Semaphore>, Error>`.
+///
+/// ```rust,ignore
+/// let sem = threadx::static_semaphore!(c"sem0", 0)?;
+/// ```
+#[macro_export]
+macro_rules! static_semaphore {
+    ($name:expr, $initial_count:expr) => {{
+        static SEMAPHORE: $crate::semaphore::StaticSemaphore =
+            $crate::semaphore::StaticSemaphore::new();
+        SEMAPHORE.create($name, $initial_count)
+    }};
+}
+
+// End of file
@@ -0,0 +1,587 @@
+//! Snapshots of every kernel object that currently exists
+//!
+//! ThreadX keeps a list of every created object of each type. A [`Snapshot`]
+//! walks all of those lists with interrupts disabled, so it is a consistent
+//! view of the system at one instant, and records what the matching
+//! `tx_*_info_get` service reports for each object.
+//!
+//! Interrupts stay disabled for the whole walk, so keep `N` small on systems
+//! with tight interrupt latency requirements.
+//!
+//! ```rust,ignore
+//! let snapshot = threadx::snapshot::Snapshot::<8>::take();
+//! for thread in &snapshot.threads {
+//!     defmt::info!("{=str} is {}", thread.name, thread.state);
+//! }
+//! ```
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use heapless::Vec;
+use threadx_sys::{
+    CHAR, TX_BLOCK_POOL, TX_BYTE_POOL, TX_EVENT_FLAGS_GROUP, TX_MUTEX, TX_QUEUE, TX_SEMAPHORE,
+    TX_THREAD, TX_TIMER, UINT, ULONG,
+};
+
+use crate::thread::ThreadState;
+
+extern "C" {
+    static _tx_thread_created_ptr: *mut TX_THREAD;
+    static _tx_thread_created_count: ULONG;
+    static _tx_queue_created_ptr: *mut TX_QUEUE;
+    static _tx_queue_created_count: ULONG;
+    static _tx_semaphore_created_ptr: *mut TX_SEMAPHORE;
+    static _tx_semaphore_created_count: ULONG;
+    static _tx_mutex_created_ptr: *mut TX_MUTEX;
+    static _tx_mutex_created_count: ULONG;
+    static _tx_event_flags_created_ptr: *mut TX_EVENT_FLAGS_GROUP;
+    static _tx_event_flags_created_count: ULONG;
+    static _tx_byte_pool_created_ptr: *mut TX_BYTE_POOL;
+    static _tx_byte_pool_created_count: ULONG;
+    static _tx_block_pool_created_ptr: *mut TX_BLOCK_POOL;
+    static _tx_block_pool_created_count: ULONG;
+    static _tx_timer_created_ptr: *mut TX_TIMER;
+    static _tx_timer_created_count: ULONG;
+}
+
+/// Identifies a kernel object, by the address of its control block.
+///
+/// Compare with `ObjectId::of(thing.as_ptr())` to find a particular object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ObjectId(usize);
+
+impl ObjectId {
+    /// Get the ID of the object with the given control block
+    pub fn of<T>(ptr: *const T) -> ObjectId {
+        ObjectId(ptr as usize)
+    }
+
+    /// Get the ID of a thread, if the pointer is not null
+    fn of_thread(ptr: *mut TX_THREAD) -> Option<ObjectId> {
+        (!ptr.is_null()).then(|| ObjectId::of(ptr))
+    }
+
+    /// Get the address of the control block
+    pub const fn addr(self) -> usize {
+        self.0
+    }
+}
+
+/// What `tx_thread_info_get` reports about a thread
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ThreadInfo {
+    /// Which thread this is
+    pub id: ObjectId,
+    /// The name of the thread
+    pub name: &'static str,
+    /// What the thread is doing
+    pub state: ThreadState,
+    /// How many times the thread has been scheduled
+    pub run_count: ULONG,
+    /// The current priority
+    pub priority: UINT,
+    /// The current preemption threshold
+    pub preemption_threshold: UINT,
+    /// The time slice, in ticks
+    pub time_slice: ULONG,
+    /// The next thread waiting on the same object as this one, if this thread
+    /// is waiting on one. ThreadX keeps each object's waiting threads in a
+    /// circular list, so this is the thread itself if it is the only one.
+    pub next_suspended: Option<ObjectId>,
+}
+
+/// What `tx_queue_info_get` reports about a queue
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct QueueInfo {
+    /// Which queue this is
+    pub id: ObjectId,
+    /// The name of the queue
+    pub name: &'static str,
+    /// How many messages are in the queue
+    pub enqueued: ULONG,
+    /// How many more messages would fit in the queue
+    pub available_storage: ULONG,
+    /// The first thread waiting on the queue, if any
+    pub first_suspended: Option<ObjectId>,
+    /// How many threads are waiting on the queue
+    pub suspended_count: ULONG,
+}
+
+/// What `tx_semaphore_info_get` reports about a semaphore
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SemaphoreInfo {
+    /// Which semaphore this is
+    pub id: ObjectId,
+    /// The name of the semaphore
+    pub name: &'static str,
+    /// The current count
+    pub count: ULONG,
+    /// The first thread waiting on the semaphore, if any
+    pub first_suspended: Option<ObjectId>,
+    /// How many threads are waiting on the semaphore
+    pub suspended_count: ULONG,
+}
+
+/// What `tx_mutex_info_get` reports about a mutex
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MutexInfo {
+    /// Which mutex this is
+    pub id: ObjectId,
+    /// The name of the mutex
+    pub name: &'static str,
+    /// How many times the owner has taken the mutex
+    pub count: ULONG,
+    /// The thread holding the mutex, if any
+    pub owner: Option<ObjectId>,
+    /// The first thread waiting on the mutex, if any
+    pub first_suspended: Option<ObjectId>,
+    /// How many threads are waiting on the mutex
+    pub suspended_count: ULONG,
+}
+
+/// What `tx_event_flags_info_get` reports about an event flags group
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EventFlagsInfo {
+    /// Which group this is
+    pub id: ObjectId,
+    /// The name of the group
+    pub name: &'static str,
+    /// The flags which are currently set
+    pub flags: ULONG,
+    /// The first thread waiting on the group, if any
+    pub first_suspended: Option<ObjectId>,
+    /// How many threads are waiting on the group
+    pub suspended_count: ULONG,
+}
+
+/// What `tx_byte_pool_info_get` reports about a byte pool
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BytePoolInfo {
+    /// Which pool this is
+    pub id: ObjectId,
+    /// The name of the pool
+    pub name: &'static str,
+    /// How many bytes are free
+    pub available_bytes: ULONG,
+    /// How many fragments the pool is split into
+    pub fragments: ULONG,
+    /// The first thread waiting on the pool, if any
+    pub first_suspended: Option<ObjectId>,
+    /// How many threads are waiting on the pool
+    pub suspended_count: ULONG,
+}
+
+/// What `tx_block_pool_info_get` reports about a block pool
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BlockPoolInfo {
+    /// Which pool this is
+    pub id: ObjectId,
+    /// The name of the pool
+    pub name: &'static str,
+    /// How many blocks are free
+    pub available_blocks: ULONG,
+    /// How many blocks the pool holds
+    pub total_blocks: ULONG,
+    /// The first thread waiting on the pool, if any
+    pub first_suspended: Option<ObjectId>,
+    /// How many threads are waiting on the pool
+    pub suspended_count: ULONG,
+}
+
+/// What `tx_timer_info_get` reports about a timer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimerInfo {
+    /// Which timer this is
+    pub id: ObjectId,
+    /// The name of the timer
+    pub name: &'static str,
+    /// Whether the timer is running
+    pub active: bool,
+    /// Ticks until the timer next expires
+    pub remaining_ticks: ULONG,
+    /// Ticks between expiries, or zero for a one-shot timer
+    pub reschedule_ticks: ULONG,
+}
+
+/// Every kernel object which existed at one instant.
+///
+/// Holds up to `N` objects of each type. If there were more, the extra ones
+/// are left out and `truncated` is set.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Snapshot<const N: usize> {
+    /// Every thread
+    pub threads: Vec<ThreadInfo, N>,
+    /// Every queue
+    pub queues: Vec<QueueInfo, N>,
+    /// Every semaphore
+    pub semaphores: Vec<SemaphoreInfo, N>,
+    /// Every mutex
+    pub mutexes: Vec<MutexInfo, N>,
+    /// Every event flags group
+    pub event_flags: Vec<EventFlagsInfo, N>,
+    /// Every byte pool
+    pub byte_pools: Vec<BytePoolInfo, N>,
+    /// Every block pool
+    pub block_pools: Vec<BlockPoolInfo, N>,
+    /// Every timer
+    pub timers: Vec<TimerInfo, N>,
+    /// Whether there were more than `N` objects of any type
+    pub truncated: bool,
+}
+
+impl<const N: usize> Snapshot<N> {
+    /// Take a snapshot of every kernel object.
+    ///
+    /// May be called from any context, including interrupts.
+    pub fn take() -> Snapshot<N> {
+        crate::context::without_interrupts(|| unsafe {
+            let mut truncated = false;
+            let threads = walk(
+                _tx_thread_created_ptr,
+                _tx_thread_created_count,
+                &mut truncated,
+                |ptr| thread_info(ptr),
+            );
+            let queues = walk(
+                _tx_queue_created_ptr,
+                _tx_queue_created_count,
+                &mut truncated,
+                |ptr| queue_info(ptr),
+            );
+            let semaphores = walk(
+                _tx_semaphore_created_ptr,
+                _tx_semaphore_created_count,
+                &mut truncated,
+                |ptr| semaphore_info(ptr),
+            );
+            let mutexes = walk(
+                _tx_mutex_created_ptr,
+                _tx_mutex_created_count,
+                &mut truncated,
+                |ptr| mutex_info(ptr),
+            );
+            let event_flags = walk(
+                _tx_event_flags_created_ptr,
+                _tx_event_flags_created_count,
+                &mut truncated,
+                |ptr| event_flags_info(ptr),
+            );
+            let byte_pools = walk(
+                _tx_byte_pool_created_ptr,
+                _tx_byte_pool_created_count,
+                &mut truncated,
+                |ptr| byte_pool_info(ptr),
+            );
+            let block_pools = walk(
+                _tx_block_pool_created_ptr,
+                _tx_block_pool_created_count,
+                &mut truncated,
+                |ptr| block_pool_info(ptr),
+            );
+            let timers = walk(
+                _tx_timer_created_ptr,
+                _tx_timer_created_count,
+                &mut truncated,
+                |ptr| timer_info(ptr),
+            );
+            Snapshot {
+                threads,
+                queues,
+                semaphores,
+                mutexes,
+                event_flags,
+                byte_pools,
+                block_pools,
+                timers,
+                truncated,
+            }
+        })
+    }
+}
+
+/// Walk one of the kernel's circular lists of created objects.
+///
+/// `info` returns the information about an object, and the next object in
+/// the list.
+///
+/// # Safety
+///
+/// Interrupts must be disabled, and `head` and `count` must be one of the
+/// kernel's created lists.
+unsafe fn walk<T, I, F, const N: usize>(
+    head: *mut T,
+    count: ULONG,
+    truncated: &mut bool,
+    mut info: F,
+) -> Vec<I, N>
+where
+    F: FnMut(*mut T) -> (I, *mut T),
+{
+    let mut result = Vec::new();
+    let mut ptr = head;
+    for _ in 0..count {
+        if ptr.is_null() {
+            break;
+        }
+        let (item, next) = info(ptr);
+        if result.push(item).is_err() {
+            *truncated = true;
+            break;
+        }
+        ptr = next;
+    }
+    result
+}
+
+/// Get the information about a thread, and the next thread in the list
+unsafe fn thread_info(ptr: *mut TX_THREAD) -> (ThreadInfo, *mut TX_THREAD) {
+    let mut name: *mut CHAR = core::ptr::null_mut();
+    let mut state = 0;
+    let mut run_count = 0;
+    let mut priority = 0;
+    let mut preemption_threshold = 0;
+    let mut time_slice = 0;
+    let mut next = core::ptr::null_mut();
+    let mut next_suspended = core::ptr::null_mut();
+    unsafe {
+        threadx_sys::_tx_thread_info_get(
+            ptr,
+            &mut name,
+            &mut state,
+            &mut run_count,
+            &mut priority,
+            &mut preemption_threshold,
+            &mut time_slice,
+            &mut next,
+            &mut next_suspended,
+        );
+    }
+    let info = ThreadInfo {
+        id: ObjectId::of(ptr),
+        name: unsafe { crate::display_name_from_ptr(name) },
+        state: ThreadState::from_raw(state),
+        run_count,
+        priority,
+        preemption_threshold,
+        time_slice,
+        next_suspended: ObjectId::of_thread(next_suspended),
+    };
+    (info, next)
+}
+
+/// Get the information about a queue, and the next queue in the list
+unsafe fn queue_info(ptr: *mut TX_QUEUE) -> (QueueInfo, *mut TX_QUEUE) {
+    let mut name: *mut CHAR = core::ptr::null_mut();
+    let mut enqueued = 0;
+    let mut available_storage = 0;
+    let mut first_suspended = core::ptr::null_mut();
+    let mut suspended_count = 0;
+    let mut next = core::ptr::null_mut();
+    unsafe {
+        threadx_sys::_tx_queue_info_get(
+            ptr,
+            &mut name,
+            &mut enqueued,
+            &mut available_storage,
+            &mut first_suspended,
+            &mut suspended_count,
+            &mut next,
+        );
+    }
+    let info = QueueInfo {
+        id: ObjectId::of(ptr),
+        name: unsafe { crate::display_name_from_ptr(name) },
+        enqueued,
+        available_storage,
+        first_suspended: ObjectId::of_thread(first_suspended),
+        suspended_count,
+    };
+    (info, next)
+}
+
+/// Get the information about a semaphore, and the next semaphore in the list
+unsafe fn semaphore_info(ptr: *mut TX_SEMAPHORE) -> (SemaphoreInfo, *mut TX_SEMAPHORE) {
+    let mut name: *mut CHAR = core::ptr::null_mut();
+    let mut count = 0;
+    let mut first_suspended = core::ptr::null_mut();
+    let mut suspended_count = 0;
+    let mut next = core::ptr::null_mut();
+    unsafe {
+        threadx_sys::_tx_semaphore_info_get(
+            ptr,
+            &mut name,
+            &mut count,
+            &mut first_suspended,
+            &mut suspended_count,
+            &mut next,
+        );
+    }
+    let info = SemaphoreInfo {
+        id: ObjectId::of(ptr),
+        name: unsafe { crate::display_name_from_ptr(name) },
+        count,
+        first_suspended: ObjectId::of_thread(first_suspended),
+        suspended_count,
+    };
+    (info, next)
+}
+
+/// Get the information about a mutex, and the next mutex in the list
+unsafe fn mutex_info(ptr: *mut TX_MUTEX) -> (MutexInfo, *mut TX_MUTEX) {
+    let mut name: *mut CHAR = core::ptr::null_mut();
+    let mut count = 0;
+    let mut owner = core::ptr::null_mut();
+    let mut first_suspended = core::ptr::null_mut();
+    let mut suspended_count = 0;
+    let mut next = core::ptr::null_mut();
+    unsafe {
+        threadx_sys::_tx_mutex_info_get(
+            ptr,
+            &mut name,
+            &mut count,
+            &mut owner,
+            &mut first_suspended,
+            &mut suspended_count,
+            &mut next,
+        );
+    }
+    let info = MutexInfo {
+        id: ObjectId::of(ptr),
+        name: unsafe { crate::display_name_from_ptr(name) },
+        count,
+        owner: ObjectId::of_thread(owner),
+        first_suspended: ObjectId::of_thread(first_suspended),
+        suspended_count,
+    };
+    (info, next)
+}
+
+/// Get the information about an event flags group, and the next group in the
+/// list
+unsafe fn event_flags_info(
+    ptr: *mut TX_EVENT_FLAGS_GROUP,
+) -> (EventFlagsInfo, *mut TX_EVENT_FLAGS_GROUP) {
+    let mut name: *mut CHAR = core::ptr::null_mut();
+    let mut flags = 0;
+    let mut first_suspended = core::ptr::null_mut();
+    let mut suspended_count = 0;
+    let mut next = core::ptr::null_mut();
+    unsafe {
+        threadx_sys::_tx_event_flags_info_get(
+            ptr,
+            &mut name,
+            &mut flags,
+            &mut first_suspended,
+            &mut suspended_count,
+            &mut next,
+        );
+    }
+    let info = EventFlagsInfo {
+        id: ObjectId::of(ptr),
+        name: unsafe { crate::display_name_from_ptr(name) },
+        flags,
+        first_suspended: ObjectId::of_thread(first_suspended),
+        suspended_count,
+    };
+    (info, next)
+}
+
+/// Get the information about a byte pool, and the next pool in the list
+unsafe fn byte_pool_info(ptr: *mut TX_BYTE_POOL) -> (BytePoolInfo, *mut TX_BYTE_POOL) {
+    let mut name: *mut CHAR = core::ptr::null_mut();
+    let mut available_bytes = 0;
+    let mut fragments = 0;
+    let mut first_suspended = core::ptr::null_mut();
+    let mut suspended_count = 0;
+    let mut next = core::ptr::null_mut();
+    unsafe {
+        threadx_sys::_tx_byte_pool_info_get(
+            ptr,
+            &mut name,
+            &mut available_bytes,
+            &mut fragments,
+            &mut first_suspended,
+            &mut suspended_count,
+            &mut next,
+        );
+    }
+    let info = BytePoolInfo {
+        id: ObjectId::of(ptr),
+        name: unsafe { crate::display_name_from_ptr(name) },
+        available_bytes,
+        fragments,
+        first_suspended: ObjectId::of_thread(first_suspended),
+        suspended_count,
+    };
+    (info, next)
+}
+
+/// Get the information about a block pool, and the next pool in the list
+unsafe fn block_pool_info(ptr: *mut TX_BLOCK_POOL) -> (BlockPoolInfo, *mut TX_BLOCK_POOL) {
+    let mut name: *mut CHAR = core::ptr::null_mut();
+    let mut available_blocks = 0;
+    let mut total_blocks = 0;
+    let mut first_suspended = core::ptr::null_mut();
+    let mut suspended_count = 0;
+    let mut next = core::ptr::null_mut();
+    unsafe {
+        threadx_sys::_tx_block_pool_info_get(
+            ptr,
+            &mut name,
+            &mut available_blocks,
+            &mut total_blocks,
+            &mut first_suspended,
+            &mut suspended_count,
+            &mut next,
+        );
+    }
+    let info = BlockPoolInfo {
+        id: ObjectId::of(ptr),
+        name: unsafe { crate::display_name_from_ptr(name) },
+        available_blocks,
+        total_blocks,
+        first_suspended: ObjectId::of_thread(first_suspended),
+        suspended_count,
+    };
+    (info, next)
+}
+
+/// Get the information about a timer, and the next timer in the list
+unsafe fn timer_info(ptr: *mut TX_TIMER) -> (TimerInfo, *mut TX_TIMER) {
+    let mut name: *mut CHAR = core::ptr::null_mut();
+    let mut active = 0;
+    let mut remaining_ticks = 0;
+    let mut reschedule_ticks = 0;
+    let mut next = core::ptr::null_mut();
+    unsafe {
+        threadx_sys::_tx_timer_info_get(
+            ptr,
+            &mut name,
+            &mut active,
+            &mut remaining_ticks,
+            &mut reschedule_ticks,
+            &mut next,
+        );
+    }
+    let info = TimerInfo {
+        id: ObjectId::of(ptr),
+        name: unsafe { crate::display_name_from_ptr(name) },
+        active: active == threadx_sys::TX_TRUE,
+        remaining_ticks,
+        reschedule_ticks,
+    };
+    (info, next)
+}
+
+// End of file
@@ -0,0 +1,113 @@
+//! Stack overflow detection and stack usage
+//!
+//! With the `stack-checking` feature, ThreadX is built with
+//! `TX_ENABLE_STACK_CHECKING`. Every thread stack is filled with a known
+//! pattern when the thread is created, and ThreadX checks the stack of each
+//! thread as it switches context.
+//!
+//! Call [`enable_overflow_detection`] during initialisation to have an
+//! overflow reported (over defmt, if the `defmt` feature is enabled) and then
+//! passed to your [`OverflowHandler`]. Use [`Thread::stack_usage`] to see how
+//! close each thread has come to the end of its stack.
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use threadx_sys::{TX_STACK_FILL, TX_THREAD, ULONG};
+
+use crate::{thread::Thread, Error};
+
+/// How much of a thread's stack has been used
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StackUsage {
+    /// The size of the stack, in bytes
+    pub size: usize,
+    /// The most stack the thread has used since it was created, in bytes
+    pub peak: usize,
+}
+
+impl StackUsage {
+    /// How many bytes of stack have never been used
+    pub const fn headroom(&self) -> usize {
+        self.size - self.peak
+    }
+}
+
+impl Thread {
+    /// Work out the peak stack usage of this thread.
+    ///
+    /// ThreadX fills the stack with [`TX_STACK_FILL`] when the thread is
+    /// created. Stacks grow downwards, so we count how many words at the
+    /// bottom of the stack still hold the pattern. The result is a little
+    /// pessimistic if the thread happened to write the pattern itself.
+    pub fn stack_usage(&self) -> StackUsage {
+        let tcb = self.as_ptr();
+        // Safety: these fields do not change once the thread has been created
+        let (start, size) = unsafe {
+            (
+                (*tcb).tx_thread_stack_start as *const ULONG,
+                (*tcb).tx_thread_stack_size as usize,
+            )
+        };
+        let words = size / core::mem::size_of::<ULONG>();
+        let mut untouched = 0;
+        // Safety: the stack belongs to the thread for as long as it exists.
+        // The thread may be writing to the top of it, so we use volatile
+        // reads, but it is never writing below its stack pointer.
+        while untouched < words && unsafe { start.add(untouched).read_volatile() } == TX_STACK_FILL
+        {
+            untouched += 1;
+        }
+        StackUsage {
+            size,
+            peak: size - (untouched * core::mem::size_of::<ULONG>()),
+        }
+    }
+}
+
+/// Called when a thread has overrun its stack.
+///
+/// This runs inside ThreadX, which may be in the scheduler or an interrupt
+/// handler, and the thread may not be the one that is running. The handler
+/// must not panic, block or call any ThreadX service. It should record what
+/// it can and then stop or reset the system, as the overrun has already
+/// corrupted whatever was below the stack.
+pub type OverflowHandler = fn(&Thread, StackUsage) -> !;
+
+/// The handler, or null if overflow detection is not enabled
+static HANDLER: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Ask ThreadX to report stack overflows to us.
+///
+/// When ThreadX finds that a thread has overrun its stack, we log the name of
+/// the thread and its stack usage over defmt (if the `defmt` feature is
+/// enabled), and then call `handler`.
+pub fn enable_overflow_detection(handler: OverflowHandler) -> Result<(), Error> {
+    HANDLER.store(handler as *mut (), Ordering::Release);
+    Error::check(unsafe { threadx_sys::_tx_thread_stack_error_notify(Some(stack_error_handler)) })
+}
+
+/// Called by ThreadX when it finds a thread has corrupted its stack
+unsafe extern "C" fn stack_error_handler(thread_ptr: *mut TX_THREAD) {
+    // Safety: ThreadX only checks threads that exist
+    let thread = unsafe { Thread::from_ptr(thread_ptr) };
+    let usage = thread.stack_usage();
+    #[cfg(feature = "defmt")]
+    defmt::error!(
+        "Stack overflow in thread {=str} ({=usize} byte stack, {=usize} bytes used)",
+        thread.display_name(),
+        usage.size,
+        usage.peak
+    );
+    // Safety: only `enable_overflow_detection` stores to HANDLER, and it
+    // stores an `OverflowHandler` before ThreadX can call us.
+    let handler: OverflowHandler = unsafe { core::mem::transmute(HANDLER.load(Ordering::Acquire)) };
+    // Panicking here would unwind through the kernel, so we leave it to the
+    // handler, which cannot return
+    handler(thread, usage)
+}
+
+// End of file
@@ -0,0 +1,640 @@
+//! Synchronisation primitives in the style of `std::sync`
+//!
+//! These are built from ThreadX mutexes, semaphores and event flags groups,
+//! so a thread which blocks on them really is suspended by the kernel.
+//!
+//! Unlike the other kernel object wrappers in this crate, [`Condvar`],
+//! [`RwLock`] and [`Barrier`] have a `const fn new` and create their ThreadX
+//! objects the first time they are used. ThreadX keeps pointers to those
+//! objects, so they must never move, which is why their methods take
+//! `&'static self` - put them in a `static`:
+//!
+//! ```rust,ignore
+//! static CONFIG: threadx::sync::RwLock<Config> = threadx::sync::RwLock::new(Config::DEFAULT);
+//!
+//! let config = CONFIG.read(ctx, Wait::FOREVER)?;
+//! ```
+//!
+//! [`Once`] and [`LazyLock`] have no ThreadX objects of their own, and can be
+//! used anywhere. All of them share one mutex, which is held whilst any of
+//! them is being initialised, so they can only be used from a thread.
+//!
+//! Everything here that holds a ThreadX mutex uses priority inheritance, so a
+//! low priority thread that is holding up a high priority one runs at the
+//! higher priority until it lets go.
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::{Cell, UnsafeCell},
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU8, Ordering},
+};
+
+use threadx_sys::{TX_EVENT_FLAGS_GROUP, TX_MUTEX, TX_SEMAPHORE, TX_THREAD, UINT, ULONG};
+
+use crate::{
+    context::{Context, ThreadContext},
+    mutex::{would_deadlock, MutexGuard},
+    Error, Wait,
+};
+
+/// A ThreadX object which is created the first time it is needed
+struct LazyObject<O> {
+    inner: UnsafeCell<O>,
+    created: AtomicBool,
+}
+
+// Safety: the object is only created once, with interrupts disabled, and is
+// otherwise only accessed through ThreadX APIs.
+unsafe impl<O> Sync for LazyObject<O> {}
+
+impl<O> LazyObject<O> {
+    /// Create an empty (all-zero) control block.
+    const fn zeroed() -> LazyObject<O> {
+        LazyObject {
+            // Safety: ThreadX control blocks are plain-old-data, and all
+            // zeroes is a valid value for every field.
+            inner: UnsafeCell::new(unsafe { core::mem::zeroed() }),
+            created: AtomicBool::new(false),
+        }
+    }
+
+    /// Get a pointer to the object, if it has been created
+    fn get(&self) -> Option<*mut O> {
+        self.created
+            .load(Ordering::Acquire)
+            .then_some(self.inner.get())
+    }
+
+    /// Get a pointer to the object, creating it with `create` if required.
+    fn get_or_create(&'static self, create: unsafe fn(*mut O) -> UINT) -> Result<*mut O, Error> {
+        if let Some(ptr) = self.get() {
+            return Ok(ptr);
+        }
+        crate::context::without_interrupts(|| {
+            if !self.created.load(Ordering::Relaxed) {
+                Error::check(unsafe { create(self.inner.get()) })?;
+                self.created.store(true, Ordering::Release);
+            }
+            Ok(self.inner.get())
+        })
+    }
+}
+
+/// Create a mutex with priority inheritance
+unsafe fn create_mutex(mutex: *mut TX_MUTEX) -> UINT {
+    unsafe {
+        threadx_sys::_tx_mutex_create(
+            mutex,
+            crate::name_ptr(c"sync-mutex"),
+            threadx_sys::TX_INHERIT,
+        )
+    }
+}
+
+/// Create a semaphore with a count of zero
+unsafe fn create_semaphore(semaphore: *mut TX_SEMAPHORE) -> UINT {
+    unsafe { threadx_sys::_tx_semaphore_create(semaphore, crate::name_ptr(c"sync-semaphore"), 0) }
+}
+
+/// Create an event flags group
+unsafe fn create_event_flags(group: *mut TX_EVENT_FLAGS_GROUP) -> UINT {
+    unsafe { threadx_sys::_tx_event_flags_create(group, crate::name_ptr(c"sync-flags")) }
+}
+
+/// Does the current thread hold the given mutex?
+fn holds(mutex: *mut TX_MUTEX) -> bool {
+    let current = unsafe { threadx_sys::_tx_thread_identify() };
+    let owner = unsafe { core::ptr::addr_of!((*mutex).tx_mutex_owner).read_volatile() };
+    !current.is_null() && owner == current
+}
+
+fn mutex_put(mutex: *mut TX_MUTEX) {
+    let _ = unsafe { threadx_sys::_tx_mutex_put(mutex) };
+}
+
+fn flags_set(group: *mut TX_EVENT_FLAGS_GROUP, flags: ULONG) {
+    let _ = unsafe { threadx_sys::_tx_event_flags_set(group, flags, threadx_sys::TX_OR) };
+}
+
+fn flags_clear(group: *mut TX_EVENT_FLAGS_GROUP, flags: ULONG) {
+    let _ = unsafe { threadx_sys::_tx_event_flags_set(group, !flags, threadx_sys::TX_AND) };
+}
+
+fn flags_wait(group: *mut TX_EVENT_FLAGS_GROUP, flags: ULONG, wait: Wait) -> Result<(), Error> {
+    let mut actual = 0;
+    Error::check(unsafe {
+        threadx_sys::_tx_event_flags_get(
+            group,
+            flags,
+            threadx_sys::TX_OR,
+            &mut actual,
+            wait.as_raw(),
+        )
+    })
+}
+
+/// A condition variable, for waiting on a [`Mutex`](crate::mutex::Mutex)
+/// until something changes.
+///
+/// Always use the same mutex with the same condition variable. Wake-ups can
+/// be spurious, so check the condition again after waiting, or use
+/// [`Condvar::wait_while`].
+pub struct Condvar {
+    semaphore: LazyObject<TX_SEMAPHORE>,
+    /// Threads waiting, which nobody has woken yet
+    waiters: AtomicU32,
+}
+
+/// Whether [`Condvar::wait_timeout`] gave up waiting
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WaitTimeoutResult(bool);
+
+impl WaitTimeoutResult {
+    /// Did we give up waiting, rather than being woken?
+    pub fn timed_out(&self) -> bool {
+        self.0
+    }
+}
+
+impl Condvar {
+    /// Make a condition variable.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Condvar {
+        Condvar {
+            semaphore: LazyObject::zeroed(),
+            waiters: AtomicU32::new(0),
+        }
+    }
+
+    /// Release the mutex, wait to be woken, then lock the mutex again.
+    pub fn wait<'a, T>(
+        &'static self,
+        ctx: ThreadContext,
+        guard: MutexGuard<'a, T>,
+    ) -> Result<MutexGuard<'a, T>, Error> {
+        self.wait_timeout(ctx, guard, Wait::FOREVER)
+            .map(|(guard, _)| guard)
+    }
+
+    /// Wait until `condition` returns false, releasing the mutex whilst we
+    /// wait.
+    pub fn wait_while<'a, T, F>(
+        &'static self,
+        ctx: ThreadContext,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: F,
+    ) -> Result<MutexGuard<'a, T>, Error>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut guard) {
+            guard = self.wait(ctx, guard)?;
+        }
+        Ok(guard)
+    }
+
+    /// Release the mutex, wait to be woken or for `wait` to pass, then lock
+    /// the mutex again.
+    pub fn wait_timeout<'a, T>(
+        &'static self,
+        _ctx: ThreadContext,
+        guard: MutexGuard<'a, T>,
+        wait: Wait,
+    ) -> Result<(MutexGuard<'a, T>, WaitTimeoutResult), Error> {
+        let semaphore = self.semaphore.get_or_create(create_semaphore)?;
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        let mutex = MutexGuard::leak(guard);
+        mutex_put(mutex.as_ptr());
+
+        let res = Error::check(unsafe { threadx_sys::_tx_semaphore_get(semaphore, wait.as_raw()) });
+        let mut timed_out = false;
+        if res.is_err() {
+            // Take ourselves off the count of waiters. If we are not on it, a
+            // notifier has already counted us as woken, and has put (or is
+            // about to put) a wake-up in the semaphore for us.
+            if self
+                .waiters
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                timed_out = true;
+            } else {
+                let _ = unsafe {
+                    threadx_sys::_tx_semaphore_get(semaphore, threadx_sys::TX_WAIT_FOREVER)
+                };
+            }
+        }
+
+        // No deadlock check here: failing now would lose both the guard and
+        // the wake-up, and we held this mutex when we started waiting.
+        Error::check(unsafe {
+            threadx_sys::_tx_mutex_get(mutex.as_ptr(), threadx_sys::TX_WAIT_FOREVER)
+        })?;
+        // Safety: we have just got the mutex back
+        let guard = unsafe { MutexGuard::new(mutex) };
+        match res {
+            Ok(()) | Err(Error::NoInstance) => Ok((guard, WaitTimeoutResult(timed_out))),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Wake up the highest priority waiting thread, if there is one.
+    pub fn notify_one(&'static self, _ctx: impl Context) -> Result<(), Error> {
+        let Some(semaphore) = self.semaphore.get() else {
+            return Ok(());
+        };
+        if self
+            .waiters
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_err()
+        {
+            return Ok(());
+        }
+        // Semaphores wake waiters in the order they arrived, unless told
+        Error::check(unsafe { threadx_sys::_tx_semaphore_prioritize(semaphore) })?;
+        Error::check(unsafe { threadx_sys::_tx_semaphore_put(semaphore) })
+    }
+
+    /// Wake up every waiting thread.
+    pub fn notify_all(&'static self, _ctx: impl Context) -> Result<(), Error> {
+        let Some(semaphore) = self.semaphore.get() else {
+            return Ok(());
+        };
+        for _ in 0..self.waiters.swap(0, Ordering::SeqCst) {
+            Error::check(unsafe { threadx_sys::_tx_semaphore_put(semaphore) })?;
+        }
+        Ok(())
+    }
+}
+
+/// The event flag set whilst an [`RwLock`] has no readers
+const NO_READERS: ULONG = 1;
+
+/// A reader-writer lock, which allows many readers or one writer.
+///
+/// A writer holds the lock's mutex, so readers who arrive whilst a writer is
+/// waiting or writing queue up behind it, and the writer inherits their
+/// priority. A writer waiting for readers to finish does not raise their
+/// priority.
+///
+/// A thread which holds a read lock must not ask for a write lock on the
+/// same `RwLock`, as it would wait for itself forever.
+pub struct RwLock<T> {
+    mutex: LazyObject<TX_MUTEX>,
+    flags: LazyObject<TX_EVENT_FLAGS_GROUP>,
+    readers: AtomicU32,
+    data: UnsafeCell<T>,
+}
+
+// Safety: the data is only written whilst there are no readers, and only read
+// whilst there is no writer.
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    /// Make a reader-writer lock, protecting the given value.
+    pub const fn new(value: T) -> RwLock<T> {
+        RwLock {
+            mutex: LazyObject::zeroed(),
+            flags: LazyObject::zeroed(),
+            readers: AtomicU32::new(0),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    fn objects(&'static self) -> Result<(*mut TX_MUTEX, *mut TX_EVENT_FLAGS_GROUP), Error> {
+        Ok((
+            self.mutex.get_or_create(create_mutex)?,
+            self.flags.get_or_create(create_event_flags)?,
+        ))
+    }
+
+    /// Get shared access to the data, waiting for any writer to finish.
+    ///
+    /// Returns [`Error::WouldDeadlock`] if this thread holds the write lock.
+    pub fn read(
+        &'static self,
+        _ctx: ThreadContext,
+        wait: Wait,
+    ) -> Result<RwLockReadGuard<'static, T>, Error> {
+        let (mutex, flags) = self.objects()?;
+        if holds(mutex) || (wait != Wait::NONE && would_deadlock(mutex)) {
+            return Err(Error::WouldDeadlock);
+        }
+        Error::check(unsafe { threadx_sys::_tx_mutex_get(mutex, wait.as_raw()) })?;
+        self.readers.fetch_add(1, Ordering::SeqCst);
+        mutex_put(mutex);
+        Ok(RwLockReadGuard {
+            lock: self,
+            flags,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Get exclusive access to the data, waiting for any readers or writer
+    /// to finish.
+    ///
+    /// `wait` applies separately to waiting for the writer and waiting for
+    /// the readers. Returns [`Error::WouldDeadlock`] if this thread already
+    /// holds the write lock.
+    pub fn write(
+        &'static self,
+        _ctx: ThreadContext,
+        wait: Wait,
+    ) -> Result<RwLockWriteGuard<'static, T>, Error> {
+        let (mutex, flags) = self.objects()?;
+        if holds(mutex) || (wait != Wait::NONE && would_deadlock(mutex)) {
+            return Err(Error::WouldDeadlock);
+        }
+        Error::check(unsafe { threadx_sys::_tx_mutex_get(mutex, wait.as_raw()) })?;
+        // New readers need the mutex, so the count can only go down now
+        loop {
+            flags_clear(flags, NO_READERS);
+            if self.readers.load(Ordering::SeqCst) == 0 {
+                break;
+            }
+            if let Err(e) = flags_wait(flags, NO_READERS, wait) {
+                mutex_put(mutex);
+                return Err(e);
+            }
+        }
+        Ok(RwLockWriteGuard {
+            lock: self,
+            mutex,
+            _not_send: PhantomData,
+        })
+    }
+}
+
+/// Proof that we have shared access to an [`RwLock`].
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    flags: *mut TX_EVENT_FLAGS_GROUP,
+    _not_send: PhantomData<*const ()>,
+}
+
+// Safety: a shared guard only hands out `&T`
+unsafe impl<T: Sync> Sync for RwLockReadGuard<'_, T> {}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: there is no writer whilst we exist
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        if self.lock.readers.fetch_sub(1, Ordering::SeqCst) == 1 {
+            flags_set(self.flags, NO_READERS);
+        }
+    }
+}
+
+/// Proof that we have exclusive access to an [`RwLock`].
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    mutex: *mut TX_MUTEX,
+    // ThreadX mutexes must be released by the thread that took them
+    _not_send: PhantomData<*const ()>,
+}
+
+// Safety: a shared guard only hands out `&T`
+unsafe impl<T: Sync> Sync for RwLockWriteGuard<'_, T> {}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: there are no readers or other writers whilst we exist
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // Safety: there are no readers or other writers whilst we exist
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        mutex_put(self.mutex);
+    }
+}
+
+/// Lets a fixed number of threads wait until they have all arrived.
+pub struct Barrier {
+    mutex: LazyObject<TX_MUTEX>,
+    flags: LazyObject<TX_EVENT_FLAGS_GROUP>,
+    /// How many threads have arrived, and how many times the barrier has
+    /// been passed. Only touched whilst holding the mutex.
+    state: UnsafeCell<(u32, u32)>,
+    threads: u32,
+}
+
+// Safety: the state is only accessed whilst holding the mutex
+unsafe impl Sync for Barrier {}
+
+/// What [`Barrier::wait`] tells each thread
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// Was this thread the last to arrive? Exactly one thread is told this
+    /// each time the barrier is passed.
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl Barrier {
+    /// Make a barrier for the given number of threads.
+    pub const fn new(threads: u32) -> Barrier {
+        Barrier {
+            mutex: LazyObject::zeroed(),
+            flags: LazyObject::zeroed(),
+            state: UnsafeCell::new((0, 0)),
+            threads,
+        }
+    }
+
+    /// Wait until all the threads have called `wait`.
+    pub fn wait(&'static self, _ctx: ThreadContext) -> Result<BarrierWaitResult, Error> {
+        let mutex = self.mutex.get_or_create(create_mutex)?;
+        let flags = self.flags.get_or_create(create_event_flags)?;
+        if would_deadlock(mutex) {
+            return Err(Error::WouldDeadlock);
+        }
+        Error::check(unsafe { threadx_sys::_tx_mutex_get(mutex, threadx_sys::TX_WAIT_FOREVER) })?;
+        // Safety: we hold the mutex
+        let (arrived, generation) = unsafe { &mut *self.state.get() };
+        // Alternate between two flags, so threads still waking up from one
+        // pass are not confused by the next. Nobody can be waiting for the
+        // other flag, as we have not all got here yet.
+        let flag = 1 << (*generation & 1);
+        *arrived += 1;
+        if *arrived >= self.threads {
+            *arrived = 0;
+            *generation = generation.wrapping_add(1);
+            flags_clear(flags, 1 << (*generation & 1));
+            flags_set(flags, flag);
+            mutex_put(mutex);
+            return Ok(BarrierWaitResult(true));
+        }
+        mutex_put(mutex);
+        flags_wait(flags, flag, Wait::FOREVER)?;
+        Ok(BarrierWaitResult(false))
+    }
+}
+
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+
+/// Runs a piece of initialisation exactly once.
+pub struct Once {
+    /// Held whilst the initialisation runs
+    mutex: LazyObject<TX_MUTEX>,
+    state: AtomicU8,
+    /// The thread running the initialisation, if it is a thread
+    runner: AtomicPtr<TX_THREAD>,
+}
+
+impl Once {
+    /// Make a `Once` which has not been run.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Once {
+        Once {
+            mutex: LazyObject::zeroed(),
+            state: AtomicU8::new(INCOMPLETE),
+            runner: AtomicPtr::new(core::ptr::null_mut()),
+        }
+    }
+
+    /// Has the initialisation finished?
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+
+    /// Run `f`, unless it (or another closure given to this `Once`) has
+    /// already run.
+    ///
+    /// If another thread is running the initialisation, wait for it to
+    /// finish, lending it our priority if that is higher. If the thread that
+    /// was running it is terminated (for example because it panicked), the
+    /// next caller runs its own closure instead.
+    ///
+    /// # Panics
+    ///
+    /// If called from within `f`.
+    pub fn call_once<F>(&'static self, _ctx: ThreadContext, f: F)
+    where
+        F: FnOnce(),
+    {
+        if self.is_completed() {
+            return;
+        }
+        let thread = crate::context::current_thread().expect("ThreadContext outside a thread");
+        let mutex = self
+            .mutex
+            .get_or_create(create_mutex)
+            .expect("Creating the Once mutex");
+        Error::check(unsafe { threadx_sys::_tx_mutex_get(mutex, threadx_sys::TX_WAIT_FOREVER) })
+            .expect("Getting the Once mutex");
+        loop {
+            let state = self.state.load(Ordering::Acquire);
+            if state == COMPLETE {
+                break;
+            }
+            if state == RUNNING && self.runner.load(Ordering::Relaxed) == thread.as_ptr() {
+                mutex_put(mutex);
+                panic!("Once used recursively");
+            }
+            if self
+                .state
+                .compare_exchange(state, RUNNING, Ordering::Acquire, Ordering::Acquire)
+                .is_ok()
+            {
+                self.runner.store(thread.as_ptr(), Ordering::Relaxed);
+                self.run(f);
+                break;
+            }
+        }
+        mutex_put(mutex);
+    }
+
+    fn run<F>(&self, f: F)
+    where
+        F: FnOnce(),
+    {
+        f();
+        self.runner.store(core::ptr::null_mut(), Ordering::Relaxed);
+        self.state.store(COMPLETE, Ordering::Release);
+    }
+}
+
+/// A value which is initialised the first time it is used.
+///
+/// Unlike `std::sync::LazyLock`, this does not implement `Deref`, as getting
+/// the value may block, which needs a [`ThreadContext`].
+///
+/// ```rust,ignore
+/// static TABLE: threadx::sync::LazyLock<[u32; 256]> = threadx::sync::LazyLock::new(make_table);
+///
+/// let entry = LazyLock::force(&TABLE, ctx)[42];
+/// ```
+pub struct LazyLock<T, F = fn() -> T> {
+    once: Once,
+    init: Cell<Option<F>>,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Safety: `init` is only touched by whoever runs the `Once`, and `value` is
+// only read once the `Once` has completed.
+unsafe impl<T: Send + Sync, F: Send> Sync for LazyLock<T, F> {}
+
+impl<T, F: FnOnce() -> T> LazyLock<T, F> {
+    /// Make a value which will be initialised with `f`.
+    pub const fn new(f: F) -> LazyLock<T, F> {
+        LazyLock {
+            once: Once::new(),
+            init: Cell::new(Some(f)),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Initialise the value if required, and get it.
+    ///
+    /// # Panics
+    ///
+    /// If the initialiser was started before, but its thread was terminated
+    /// before it finished. Also as for [`Once::call_once`].
+    pub fn force(this: &'static LazyLock<T, F>, ctx: ThreadContext) -> &'static T {
+        this.once.call_once(ctx, || {
+            let f = this.init.take().expect("LazyLock initialiser already used");
+            // Safety: only the `Once` can write to the value
+            unsafe { (*this.value.get()).write(f()) };
+        });
+        // Safety: the `Once` has completed, so the value has been written
+        unsafe { (*this.value.get()).assume_init_ref() }
+    }
+}
+
+impl<T, F> Drop for LazyLock<T, F> {
+    fn drop(&mut self) {
+        if self.once.is_completed() {
+            // Safety: the value was written when the `Once` completed
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+// End of file
@@ -0,0 +1,1282 @@
+//! ThreadX threads
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::UnsafeCell,
+    ffi::CStr,
+    marker::{PhantomData, PhantomPinned},
+    mem::ManuallyDrop,
+    pin::Pin,
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, Ordering},
+};
+
+use threadx_sys::{TX_SEMAPHORE, TX_THREAD, UINT, ULONG};
+
+use crate::{byte_pool::BytePool, context::ThreadContext, Error, Wait};
+
+/// The function a ThreadX thread starts executing
+pub type ThreadEntry = extern "C" fn(ULONG);
+
+/// A ThreadX thread control block.
+///
+/// You only ever see these behind a shared reference, because ThreadX
+/// modifies the control block whenever it schedules the thread.
+#[repr(transparent)]
+pub struct Thread {
+    inner: UnsafeCell<TX_THREAD>,
+    _pin: PhantomPinned,
+}
+
+// Safety: every access to the inner control block goes via a ThreadX API,
+// which handles the locking.
+unsafe impl Sync for Thread {}
+
+impl Thread {
+    /// Create an empty (all-zero) control block.
+    const fn zeroed() -> Thread {
+        Thread {
+            // Safety: TX_THREAD is plain-old-data, and all zeroes is a valid
+            // value for every field.
+            inner: UnsafeCell::new(unsafe { core::mem::zeroed() }),
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Get a reference to a thread from a raw pointer to its control block.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a thread control block which has been created and
+    /// which will not be deleted whilst the reference exists.
+    pub(crate) unsafe fn from_ptr<'a>(ptr: *mut TX_THREAD) -> &'a Thread {
+        // Safety: Thread is a transparent wrapper around TX_THREAD
+        unsafe { &*(ptr as *const Thread) }
+    }
+
+    /// Get a raw pointer to the ThreadX control block
+    pub fn as_ptr(&self) -> *mut TX_THREAD {
+        self.inner.get()
+    }
+
+    /// Get a handle to this thread which can be kept forever.
+    ///
+    /// Returns `None` for a [spawned](spawn) thread, as its control block is
+    /// freed when it is joined.
+    pub fn to_static(self: Pin<&Self>) -> Option<Pin<&'static Thread>> {
+        if self.is_spawned() {
+            return None;
+        }
+        // Safety: a thread which was not spawned was created in a `static`,
+        // and deleting it is `unsafe`
+        Some(Pin::static_ref(unsafe { Thread::from_ptr(self.as_ptr()) }))
+    }
+
+    /// Was the thread created by [`spawn`], or in a [`Scope`]?
+    fn is_spawned(&self) -> bool {
+        unsafe { core::ptr::addr_of!((*self.as_ptr()).tx_thread_rust_spawned).read_volatile() != 0 }
+    }
+
+    /// Get the name the thread was created with
+    pub fn name(&self) -> Option<&'static CStr> {
+        // Safety: the name pointer was given to us as a `&'static CStr`
+        unsafe { crate::name_from_ptr((*self.as_ptr()).tx_thread_name) }
+    }
+
+    /// Get the name the thread was created with, as a string.
+    ///
+    /// Gives a placeholder if the thread has no name, or if the name is not
+    /// valid UTF-8.
+    pub fn display_name(&self) -> &'static str {
+        // Safety: the name pointer was given to us as a `&'static CStr`
+        unsafe { crate::display_name_from_ptr((*self.as_ptr()).tx_thread_name) }
+    }
+
+    /// Get the current state of the thread
+    pub fn state(&self) -> ThreadState {
+        ThreadState::from_raw(unsafe {
+            core::ptr::addr_of!((*self.as_ptr()).tx_thread_state).read_volatile()
+        })
+    }
+
+    /// Get the priority the thread was given, when it was created or by
+    /// [`priority_change`](Self::priority_change).
+    ///
+    /// Whilst the thread holds a mutex with priority inheritance, it may be
+    /// running at a higher priority than this.
+    pub fn priority(&self) -> Priority {
+        Priority(unsafe {
+            core::ptr::addr_of!((*self.as_ptr()).tx_thread_user_priority).read_volatile()
+        })
+    }
+
+    /// Get the preemption threshold the thread was given
+    pub fn preempt_threshold(&self) -> PreemptThreshold {
+        PreemptThreshold(unsafe {
+            core::ptr::addr_of!((*self.as_ptr()).tx_thread_user_preempt_threshold).read_volatile()
+        })
+    }
+
+    /// Suspend the thread until it is [resumed](Self::resume).
+    ///
+    /// A thread may suspend itself. A thread which is waiting for something
+    /// else is suspended as soon as that wait is over.
+    ///
+    /// Returns [`Error::SuspendError`] if the thread has completed or been
+    /// terminated.
+    pub fn suspend(&self) -> Result<(), Error> {
+        if self.is_finished() {
+            return Err(Error::SuspendError);
+        }
+        Error::check(unsafe { threadx_sys::_tx_thread_suspend(self.as_ptr()) })
+    }
+
+    /// Resume a thread that was created without auto-start, or which was
+    /// suspended.
+    ///
+    /// Returns [`Error::ResumeError`] if the thread has completed or been
+    /// terminated - [reset](Self::reset) it first.
+    pub fn resume(&self) -> Result<(), Error> {
+        if self.is_finished() {
+            return Err(Error::ResumeError);
+        }
+        Error::check(unsafe { threadx_sys::_tx_thread_resume(self.as_ptr()) })
+    }
+
+    /// Change the thread's priority, returning the old one.
+    ///
+    /// As in ThreadX, this also sets the preemption threshold to the new
+    /// priority, which disables preemption-threshold scheduling.
+    pub fn priority_change(&self, priority: Priority) -> Result<Priority, Error> {
+        let mut old = 0;
+        Error::check(unsafe {
+            threadx_sys::_tx_thread_priority_change(self.as_ptr(), priority.get(), &mut old)
+        })?;
+        Ok(Priority(old))
+    }
+
+    /// Change the thread's preemption threshold, returning the old one.
+    ///
+    /// Returns [`Error::ThreshError`] if the threshold is lower priority than
+    /// the thread itself.
+    pub fn preemption_change(
+        &self,
+        threshold: PreemptThreshold,
+    ) -> Result<PreemptThreshold, Error> {
+        if !threshold.allows(self.priority()) {
+            return Err(Error::ThreshError);
+        }
+        let mut old = 0;
+        Error::check(unsafe {
+            threadx_sys::_tx_thread_preemption_change(self.as_ptr(), threshold.get(), &mut old)
+        })?;
+        Ok(PreemptThreshold(old))
+    }
+
+    /// Change the thread's time slice, returning the old one.
+    ///
+    /// Give [`threadx_sys::TX_NO_TIME_SLICE`] to disable time slicing.
+    pub fn time_slice_change(&self, time_slice: ULONG) -> Result<ULONG, Error> {
+        let mut old = 0;
+        Error::check(unsafe {
+            threadx_sys::_tx_thread_time_slice_change(self.as_ptr(), time_slice, &mut old)
+        })?;
+        Ok(old)
+    }
+
+    /// Make the thread give up whatever it is waiting for (sleeping, or a
+    /// kernel object), so that the service it called returns
+    /// [`Error::WaitAborted`].
+    ///
+    /// Returns [`Error::WaitAbortError`] if the thread is not waiting. A
+    /// thread which was [suspended](Self::suspend) must be resumed instead.
+    pub fn wait_abort(&self) -> Result<(), Error> {
+        if matches!(
+            self.state(),
+            ThreadState::Ready
+                | ThreadState::Completed
+                | ThreadState::Terminated
+                | ThreadState::Suspended
+        ) {
+            return Err(Error::WaitAbortError);
+        }
+        Error::check(unsafe { threadx_sys::_tx_thread_wait_abort(self.as_ptr()) })
+    }
+
+    /// Terminate a thread, whatever state it is in.
+    ///
+    /// Any mutexes the thread holds are released, so the data they protect
+    /// may have been left half-modified. A thread may terminate itself.
+    ///
+    /// Returns [`Error::CallerError`] if the thread is running a [`scope`].
+    ///
+    /// # Safety
+    ///
+    /// The thread's stack is not unwound, so nothing on it is dropped. Nothing
+    /// on the thread's stack may rely on being dropped before its memory is
+    /// reused or freed (for example, a value pinned with `core::pin::pin!`),
+    /// and nothing else may be borrowing from the thread's stack.
+    pub unsafe fn terminate(&self) -> Result<(), Error> {
+        let terminate = || {
+            if self.has_scopes() {
+                return Err(Error::CallerError);
+            }
+            Error::check(unsafe { threadx_sys::_tx_thread_terminate(self.as_ptr()) })
+        };
+        match crate::context::current_thread() {
+            // We cannot be starting a scope whilst we are in here
+            Some(current) if current.as_ptr() == self.as_ptr() => terminate(),
+            // Stop the thread starting a scope between us looking and
+            // terminating it
+            Some(current) => without_preemption(current.as_ptr(), terminate),
+            None => terminate(),
+        }
+    }
+
+    /// Reset a thread which has completed or been terminated, so that it
+    /// starts again from its entry function when it is next resumed.
+    ///
+    /// Any [thread-local](crate::tls) values belonging to the thread are
+    /// dropped first, so the thread starts again with fresh ones.
+    ///
+    /// Returns [`Error::CallerError`] if the thread was terminated whilst it
+    /// was running a [`scope`], which [`Thread::terminate`] does not allow,
+    /// or if the thread was [spawned](spawn), as whoever joins it expects it
+    /// to finish only once.
+    ///
+    /// # Safety
+    ///
+    /// The thread's stack is reused, so the requirements of
+    /// [`Thread::terminate`] apply to whatever was left on it, even if the
+    /// thread completed by returning from its entry function.
+    pub unsafe fn reset(&self) -> Result<(), Error> {
+        if !self.is_finished() {
+            return Err(Error::NotDone);
+        }
+        if self.has_scopes() || self.is_spawned() {
+            return Err(Error::CallerError);
+        }
+        let panicking =
+            unsafe { core::ptr::addr_of_mut!((*self.as_ptr()).tx_thread_rust_panicking) };
+        unsafe {
+            crate::tls::destroy(self.as_ptr());
+        }
+        Error::check(unsafe { threadx_sys::_tx_thread_reset(self.as_ptr()) })?;
+        if unsafe { panicking.read_volatile() } != 0 {
+            // Panic isolation stopped anything preempting the thread until it
+            // had terminated, so put back the threshold it had before
+            let threshold = unsafe {
+                core::ptr::addr_of!((*self.as_ptr()).tx_thread_rust_threshold).read_volatile()
+            };
+            unsafe { panicking.write_volatile(0) };
+            let mut old = 0;
+            Error::check(unsafe {
+                threadx_sys::_tx_thread_preemption_change(self.as_ptr(), threshold, &mut old)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Is the thread running a [`scope`], whose threads may be borrowing from
+    /// its stack?
+    fn has_scopes(&self) -> bool {
+        !unsafe { scope_head(self.as_ptr()).read_volatile() }.is_null()
+    }
+
+    /// Has the thread completed, or been terminated?
+    fn is_finished(&self) -> bool {
+        matches!(
+            self.state(),
+            ThreadState::Completed | ThreadState::Terminated
+        )
+    }
+
+    /// Delete a thread which has completed or been terminated.
+    ///
+    /// Any [thread-local](crate::tls) values belonging to the thread are
+    /// dropped first.
+    ///
+    /// # Safety
+    ///
+    /// The thread must not be used again after it has been deleted.
+    pub unsafe fn delete(&self) -> Result<(), Error> {
+        if !self.is_finished() {
+            return Err(Error::DeleteError);
+        }
+        unsafe {
+            crate::tls::destroy(self.as_ptr());
+        }
+        Error::check(unsafe { threadx_sys::_tx_thread_delete(self.as_ptr()) })
+    }
+}
+
+impl core::fmt::Debug for Thread {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Thread")
+            .field("name", &self.name())
+            .field("ptr", &self.as_ptr())
+            .finish()
+    }
+}
+
+/// What a thread is doing
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ThreadState {
+    /// Running, or ready to run
+    Ready,
+    /// Returned from its entry function
+    Completed,
+    /// Terminated
+    Terminated,
+    /// Suspended with `tx_thread_suspend`, or created without auto-start
+    Suspended,
+    /// Sleeping
+    Sleeping,
+    /// Waiting on a queue
+    QueueSuspended,
+    /// Waiting on a semaphore
+    SemaphoreSuspended,
+    /// Waiting on an event flags group
+    EventFlagsSuspended,
+    /// Waiting on a block pool
+    BlockPoolSuspended,
+    /// Waiting on a byte pool
+    BytePoolSuspended,
+    /// Waiting on a mutex
+    MutexSuspended,
+    /// Some other state, such as waiting on a FileX or NetX object
+    Other(UINT),
+}
+
+impl ThreadState {
+    /// Convert from the ThreadX `tx_thread_state` value
+    pub const fn from_raw(state: UINT) -> ThreadState {
+        match state {
+            threadx_sys::TX_READY => ThreadState::Ready,
+            threadx_sys::TX_COMPLETED => ThreadState::Completed,
+            threadx_sys::TX_TERMINATED => ThreadState::Terminated,
+            threadx_sys::TX_SUSPENDED => ThreadState::Suspended,
+            threadx_sys::TX_SLEEP => ThreadState::Sleeping,
+            threadx_sys::TX_QUEUE_SUSP => ThreadState::QueueSuspended,
+            threadx_sys::TX_SEMAPHORE_SUSP => ThreadState::SemaphoreSuspended,
+            threadx_sys::TX_EVENT_FLAG => ThreadState::EventFlagsSuspended,
+            threadx_sys::TX_BLOCK_MEMORY => ThreadState::BlockPoolSuspended,
+            threadx_sys::TX_BYTE_MEMORY => ThreadState::BytePoolSuspended,
+            threadx_sys::TX_MUTEX_SUSP => ThreadState::MutexSuspended,
+            other => ThreadState::Other(other),
+        }
+    }
+}
+
+/// A thread priority, where 0 is the highest.
+///
+/// Always less than [`threadx_sys::TX_MAX_PRIORITIES`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Priority(UINT);
+
+impl Priority {
+    /// The highest priority
+    pub const HIGHEST: Priority = Priority(0);
+    /// The lowest priority
+    pub const LOWEST: Priority = Priority(threadx_sys::TX_MAX_PRIORITIES - 1);
+
+    /// Make a priority.
+    ///
+    /// Use [`Priority::try_from`] for values only known at run time.
+    ///
+    /// # Panics
+    ///
+    /// If `priority` is not less than [`threadx_sys::TX_MAX_PRIORITIES`]. In
+    /// a `const`, this is a compile-time error.
+    pub const fn new(priority: u32) -> Priority {
+        assert!(
+            priority < threadx_sys::TX_MAX_PRIORITIES,
+            "Priority out of range"
+        );
+        Priority(priority)
+    }
+
+    /// Get the raw ThreadX priority
+    pub const fn get(self) -> UINT {
+        self.0
+    }
+}
+
+impl TryFrom<u32> for Priority {
+    type Error = Error;
+
+    /// Returns [`Error::PriorityError`] if the priority is out of range.
+    fn try_from(priority: u32) -> Result<Priority, Error> {
+        if priority < threadx_sys::TX_MAX_PRIORITIES {
+            Ok(Priority(priority))
+        } else {
+            Err(Error::PriorityError)
+        }
+    }
+}
+
+/// A preemption threshold. Only threads with a higher priority than this
+/// (that is, a lower number) may preempt a thread.
+///
+/// A thread's threshold cannot be lower priority than the thread itself. A
+/// threshold equal to the thread's priority disables preemption-threshold
+/// scheduling for the thread.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PreemptThreshold(UINT);
+
+impl PreemptThreshold {
+    /// Only a thread with a higher priority than the thread's own may preempt
+    /// it, which is normal scheduling.
+    pub const fn disabled(priority: Priority) -> PreemptThreshold {
+        PreemptThreshold(priority.0)
+    }
+
+    /// Make a preemption threshold.
+    ///
+    /// Use [`PreemptThreshold::try_from`] for values only known at run time.
+    ///
+    /// # Panics
+    ///
+    /// If `threshold` is not less than [`threadx_sys::TX_MAX_PRIORITIES`]. In
+    /// a `const`, this is a compile-time error.
+    pub const fn new(threshold: u32) -> PreemptThreshold {
+        assert!(
+            threshold < threadx_sys::TX_MAX_PRIORITIES,
+            "Preemption threshold out of range"
+        );
+        PreemptThreshold(threshold)
+    }
+
+    /// Get the raw ThreadX preemption threshold
+    pub const fn get(self) -> UINT {
+        self.0
+    }
+
+    /// Is this a valid threshold for a thread with the given priority?
+    const fn allows(self, priority: Priority) -> bool {
+        self.0 <= priority.0
+    }
+}
+
+impl TryFrom<u32> for PreemptThreshold {
+    type Error = Error;
+
+    /// Returns [`Error::ThreshError`] if the threshold is out of range.
+    fn try_from(threshold: u32) -> Result<PreemptThreshold, Error> {
+        if threshold < threadx_sys::TX_MAX_PRIORITIES {
+            Ok(PreemptThreshold(threshold))
+        } else {
+            Err(Error::ThreshError)
+        }
+    }
+}
+
+/// Settings for creating a thread
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ThreadOptions {
+    /// Priority
+    pub priority: Priority,
+    /// Only threads with a higher priority than this may preempt the thread
+    pub preempt_threshold: PreemptThreshold,
+    /// Number of ticks before the next ready thread of the same priority is
+    /// run, or [`threadx_sys::TX_NO_TIME_SLICE`]
+    pub time_slice: ULONG,
+    /// Start running the thread as soon as it is created
+    pub auto_start: bool,
+}
+
+impl ThreadOptions {
+    /// Options for an auto-started thread at the given priority, with no
+    /// preemption threshold and no time slicing.
+    pub const fn new(priority: Priority) -> ThreadOptions {
+        ThreadOptions {
+            priority,
+            preempt_threshold: PreemptThreshold::disabled(priority),
+            time_slice: threadx_sys::TX_NO_TIME_SLICE,
+            auto_start: true,
+        }
+    }
+
+    /// Check the options make sense together, as `_tx_thread_create` does
+    /// not.
+    fn check(&self) -> Result<(), Error> {
+        if self.preempt_threshold.allows(self.priority) {
+            Ok(())
+        } else {
+            Err(Error::ThreshError)
+        }
+    }
+}
+
+/// The stack for a thread.
+///
+/// The AAPCS requires 8-byte alignment of the stack pointer at public
+/// interfaces, so we align the whole thing.
+#[repr(C, align(8))]
+struct Stack<const N: usize>([u8; N]);
+
+/// Storage for a thread and its stack, suitable for placing in a `static`.
+///
+/// Use the [`static_thread!`](crate::static_thread) macro rather than using
+/// this type directly.
+pub struct StaticThread<const STACK_SIZE: usize> {
+    thread: Thread,
+    stack: UnsafeCell<Stack<STACK_SIZE>>,
+    created: AtomicBool,
+}
+
+// Safety: the stack is only handed to ThreadX, once, guarded by `created`.
+unsafe impl<const STACK_SIZE: usize> Sync for StaticThread<STACK_SIZE> {}
+
+impl<const STACK_SIZE: usize> StaticThread<STACK_SIZE> {
+    /// Reserve space for a thread. It ends up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticThread<STACK_SIZE> {
+        StaticThread {
+            thread: Thread::zeroed(),
+            stack: UnsafeCell::new(Stack([0u8; STACK_SIZE])),
+            created: AtomicBool::new(false),
+        }
+    }
+
+    /// Create the ThreadX thread.
+    ///
+    /// Returns [`Error::ThreadError`] if the thread has already been created.
+    pub fn create(
+        &'static self,
+        name: &'static CStr,
+        entry: ThreadEntry,
+        input: ULONG,
+        options: ThreadOptions,
+    ) -> Result<Pin<&'static Thread>, Error> {
+        options.check()?;
+        if self.created.swap(true, Ordering::AcqRel) {
+            return Err(Error::ThreadError);
+        }
+        let res = unsafe {
+            threadx_sys::_tx_thread_create(
+                self.thread.as_ptr(),
+                crate::name_ptr(name),
+                Some(entry as unsafe extern "C" fn(ULONG)),
+                input,
+                self.stack.get() as *mut _,
+                STACK_SIZE as ULONG,
+                options.priority.get(),
+                options.preempt_threshold.get(),
+                options.time_slice,
+                if options.auto_start {
+                    threadx_sys::TX_AUTO_START
+                } else {
+                    threadx_sys::TX_DONT_START
+                },
+            )
+        };
+        if let Err(e) = Error::check(res) {
+            self.created.store(false, Ordering::Release);
+            return Err(e);
+        }
+        Ok(Pin::static_ref(&self.thread))
+    }
+}
+
+/// Get the thread we are executing in, like `tx_thread_identify`.
+///
+/// Returns `None` during initialisation and in interrupt context, where
+/// `tx_thread_identify` would give whichever thread was interrupted. Also
+/// returns `None` in a [spawned](spawn) thread, whose control block is freed
+/// when it is joined - use [`with_current`] there instead.
+pub fn current() -> Option<Pin<&'static Thread>> {
+    with_current(|thread| thread.to_static()).flatten()
+}
+
+/// Call `f` with the thread we are executing in, which may be a
+/// [spawned](spawn) thread.
+///
+/// Returns `None`, without calling `f`, during initialisation and in
+/// interrupt context.
+pub fn with_current<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(Pin<&Thread>) -> R,
+{
+    let ptr = crate::context::current_thread()?;
+    // Safety: a running thread has been created, and cannot be deleted until
+    // it has stopped running. `f` cannot keep the reference.
+    Some(f(unsafe {
+        Pin::new_unchecked(Thread::from_ptr(ptr.as_ptr()))
+    }))
+}
+
+/// Run `f` without letting any other thread preempt `current`, which must be
+/// the current thread.
+fn without_preemption<F, R>(current: *mut TX_THREAD, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let mut old = 0;
+    let mut ignored = 0;
+    unsafe { threadx_sys::_tx_thread_preemption_change(current, 0, &mut old) };
+    let result = f();
+    unsafe { threadx_sys::_tx_thread_preemption_change(current, old, &mut ignored) };
+    result
+}
+
+/// Let any other ready threads of the same priority run, before the current
+/// thread carries on.
+pub fn relinquish(_ctx: ThreadContext) {
+    unsafe { threadx_sys::_tx_thread_relinquish() }
+}
+
+/// Put the current thread to sleep for the given number of timer ticks.
+pub fn sleep(_ctx: ThreadContext, ticks: u32) -> Result<(), Error> {
+    Error::check(unsafe { threadx_sys::_tx_thread_sleep(ticks as ULONG) })
+}
+
+/// The stack alignment the AAPCS requires
+const STACK_ALIGN: usize = 8;
+
+/// A spawned thread which has not finished, and can still be joined
+const SPAWN_RUNNING: u8 = 0;
+/// A spawned thread which has finished
+const SPAWN_FINISHED: u8 = 1;
+/// A spawned thread whose join handle was dropped before it finished
+const SPAWN_DETACHED: u8 = 2;
+
+/// Spawned threads whose join handles have been dropped, and which have
+/// finished (or are just about to), waiting to be freed
+static DETACHED: AtomicPtr<SpawnHeader> = AtomicPtr::new(core::ptr::null_mut());
+
+/// The start of the memory allocated for a spawned thread.
+///
+/// The thread control block comes first, so the exit notification can find
+/// the rest from the thread pointer it is given.
+#[repr(C)]
+struct SpawnHeader {
+    thread: Thread,
+    /// Put once the thread has completed or been terminated
+    done: UnsafeCell<TX_SEMAPHORE>,
+    /// What we got from the byte pool, before aligning it
+    memory: NonNull<u8>,
+    /// Drops whatever is left of the closure and its result
+    drop_contents: unsafe fn(*mut SpawnHeader),
+    /// One of `SPAWN_RUNNING`, `SPAWN_FINISHED` or `SPAWN_DETACHED`
+    state: AtomicU8,
+    /// The next thread spawned in the same [`Scope`], or the next detached
+    /// thread
+    next: *mut SpawnHeader,
+}
+
+/// The part of a spawned thread's memory which a join handle can see
+#[repr(C)]
+struct SpawnShared<T> {
+    header: SpawnHeader,
+    result: UnsafeCell<Option<T>>,
+}
+
+/// Everything allocated for a spawned thread, apart from its stack
+#[repr(C)]
+struct SpawnPacket<T, F> {
+    shared: SpawnShared<T>,
+    /// Taken by the thread when it starts
+    entry: UnsafeCell<Option<F>>,
+}
+
+/// Spawn a new thread, running `f`, with its control block and stack
+/// allocated from `pool`.
+///
+/// The thread starts straight away if `options.auto_start` is set, otherwise
+/// call [`Thread::resume`] on [`JoinHandle::thread`]. Call
+/// [`JoinHandle::join`] to wait for the thread to finish, get what `f`
+/// returned, and give the memory back to the pool. If the handle is dropped
+/// instead, the memory is given back once the thread has finished.
+///
+/// ```rust,ignore
+/// let handle = threadx::thread::spawn(
+///     ctx,
+///     pool,
+///     c"worker",
+///     4096,
+///     threadx::thread::ThreadOptions::new(threadx::thread::Priority::new(5)),
+///     || 6 * 7,
+/// )?;
+/// assert_eq!(handle.join(ctx)?, 42);
+/// ```
+pub fn spawn<F, T>(
+    ctx: ThreadContext,
+    pool: Pin<&'static BytePool>,
+    name: &'static CStr,
+    stack_size: usize,
+    options: ThreadOptions,
+    f: F,
+) -> Result<JoinHandle<T>, Error>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    reap_detached(ctx);
+    // Safety: everything the thread uses lives forever
+    let shared = unsafe { spawn_raw(ctx, pool, name, stack_size, options, f) }?;
+    Ok(JoinHandle {
+        shared,
+        _marker: PhantomData,
+    })
+}
+
+/// Allocate, create and (if asked) start a thread running `f`.
+///
+/// # Safety
+///
+/// Whatever `f` borrows must outlive the thread, and its result must be
+/// taken (or dropped) whilst whatever it borrows is still alive.
+unsafe fn spawn_raw<F, T>(
+    ctx: ThreadContext,
+    pool: Pin<&'static BytePool>,
+    name: &'static CStr,
+    stack_size: usize,
+    options: ThreadOptions,
+    f: F,
+) -> Result<NonNull<SpawnShared<T>>, Error>
+where
+    F: FnOnce() -> T,
+{
+    options.check()?;
+    let align = core::mem::align_of::<SpawnPacket<T, F>>().max(STACK_ALIGN);
+    let packet_size = core::mem::size_of::<SpawnPacket<T, F>>().next_multiple_of(STACK_ALIGN);
+    let memory = pool.allocate(ctx, align - 1 + packet_size + stack_size, Wait::NONE)?;
+    let offset = memory.as_ptr().align_offset(align);
+    let packet = unsafe { memory.as_ptr().add(offset) } as *mut SpawnPacket<T, F>;
+    let stack = unsafe { memory.as_ptr().add(offset + packet_size) };
+    unsafe {
+        packet.write(SpawnPacket {
+            shared: SpawnShared {
+                header: SpawnHeader {
+                    thread: Thread::zeroed(),
+                    // Safety: TX_SEMAPHORE is plain-old-data, and all zeroes
+                    // is a valid value for every field.
+                    done: UnsafeCell::new(core::mem::zeroed()),
+                    memory,
+                    drop_contents: drop_spawn_contents::<T, F>,
+                    state: AtomicU8::new(SPAWN_RUNNING),
+                    next: core::ptr::null_mut(),
+                },
+                result: UnsafeCell::new(None),
+            },
+            entry: UnsafeCell::new(Some(f)),
+        });
+    }
+    let header = unsafe { &(*packet).shared.header };
+
+    let res =
+        unsafe { threadx_sys::_tx_semaphore_create(header.done.get(), crate::name_ptr(name), 0) };
+    if let Err(e) = Error::check(res) {
+        unsafe {
+            core::ptr::drop_in_place(packet);
+            let _ = BytePool::release(memory);
+        }
+        return Err(e);
+    }
+    // The thread must not start until the exit notification is in place
+    let res = unsafe {
+        threadx_sys::_tx_thread_create(
+            header.thread.as_ptr(),
+            crate::name_ptr(name),
+            Some(spawn_entry::<T, F>),
+            0,
+            stack as *mut _,
+            stack_size as ULONG,
+            options.priority.get(),
+            options.preempt_threshold.get(),
+            options.time_slice,
+            threadx_sys::TX_DONT_START,
+        )
+    };
+    if let Err(e) = Error::check(res) {
+        unsafe {
+            threadx_sys::_tx_semaphore_delete(header.done.get());
+            core::ptr::drop_in_place(packet);
+            let _ = BytePool::release(memory);
+        }
+        return Err(e);
+    }
+    // `_tx_thread_create` cleared the whole control block, including this
+    unsafe {
+        core::ptr::addr_of_mut!((*header.thread.as_ptr()).tx_thread_rust_spawned).write_volatile(1);
+    }
+    let res = Error::check(unsafe {
+        threadx_sys::_tx_thread_entry_exit_notify(header.thread.as_ptr(), Some(spawn_exit))
+    })
+    .and_then(|()| {
+        if options.auto_start {
+            header.thread.resume()
+        } else {
+            Ok(())
+        }
+    });
+    if let Err(e) = res {
+        // The thread has not started, but must be terminated before ThreadX
+        // will delete it
+        unsafe {
+            threadx_sys::_tx_thread_terminate(header.thread.as_ptr());
+            threadx_sys::_tx_thread_delete(header.thread.as_ptr());
+            threadx_sys::_tx_semaphore_delete(header.done.get());
+            core::ptr::drop_in_place(packet);
+            let _ = BytePool::release(memory);
+        }
+        return Err(e);
+    }
+    Ok(unsafe { NonNull::new_unchecked(packet as *mut SpawnShared<T>) })
+}
+
+/// The entry function of every spawned thread
+extern "C" fn spawn_entry<T, F>(_input: ULONG)
+where
+    F: FnOnce() -> T,
+{
+    let Some(thread) = crate::context::current_thread() else {
+        return;
+    };
+    // Safety: this entry function is only ever given to threads whose
+    // control block is at the start of a `SpawnPacket<T, F>`.
+    let packet = thread.as_ptr() as *mut SpawnPacket<T, F>;
+    // Nothing else touches the closure or the result until we have finished
+    if let Some(f) = unsafe { (*(*packet).entry.get()).take() } {
+        let value = f();
+        unsafe { *(*packet).shared.result.get() = Some(value) };
+    }
+}
+
+/// Told by ThreadX when a spawned thread starts, completes or is terminated
+unsafe extern "C" fn spawn_exit(thread: *mut TX_THREAD, condition: UINT) {
+    if condition != threadx_sys::TX_THREAD_EXIT {
+        return;
+    }
+    // Safety: this is only registered for threads whose control block is at
+    // the start of a `SpawnHeader`.
+    let header = thread as *mut SpawnHeader;
+    // A thread which returns is only marked as completed after we have been
+    // called, so stop anything preempting it until then. Otherwise the
+    // joining thread could try to delete it whilst it is still running. A
+    // thread terminated by another one is already marked as terminated.
+    if unsafe { threadx_sys::_tx_thread_identify() } == thread {
+        let mut old_threshold = 0;
+        unsafe { threadx_sys::_tx_thread_preemption_change(thread, 0, &mut old_threshold) };
+    }
+    match unsafe { (*header).state.swap(SPAWN_FINISHED, Ordering::AcqRel) } {
+        SPAWN_RUNNING => unsafe {
+            threadx_sys::_tx_semaphore_put((*header).done.get());
+        },
+        // Nobody will join it, so it is freed once it is marked as finished
+        SPAWN_DETACHED => unsafe { push_spawned(&DETACHED, header) },
+        _ => {}
+    }
+}
+
+/// Add a spawned thread to a list of them.
+///
+/// # Safety
+///
+/// Nothing else may be using the thread's `next` pointer.
+unsafe fn push_spawned(list: &AtomicPtr<SpawnHeader>, header: *mut SpawnHeader) {
+    let mut head = list.load(Ordering::Relaxed);
+    loop {
+        unsafe { (*header).next = head };
+        match list.compare_exchange_weak(head, header, Ordering::AcqRel, Ordering::Relaxed) {
+            Ok(_) => break,
+            Err(current) => head = current,
+        }
+    }
+}
+
+/// Free every detached thread which ThreadX has marked as finished.
+///
+/// A detached thread which returns is put on the list just before it is
+/// marked as completed, so any which are not finished yet are put back to be
+/// freed next time.
+fn reap_detached(_ctx: ThreadContext) {
+    let mut header = DETACHED.swap(core::ptr::null_mut(), Ordering::AcqRel);
+    while !header.is_null() {
+        // Safety: the threads on the list have no join handles, and nobody
+        // else follows the list once we have taken it
+        unsafe {
+            let next = (*header).next;
+            if (*header).thread.is_finished() {
+                let _ = spawn_free(header);
+            } else {
+                push_spawned(&DETACHED, header);
+            }
+            header = next;
+        }
+    }
+}
+
+/// Drop the closure and result of a spawned thread, if they are still there
+unsafe fn drop_spawn_contents<T, F>(header: *mut SpawnHeader) {
+    let packet = header as *mut SpawnPacket<T, F>;
+    unsafe {
+        core::ptr::drop_in_place((*packet).entry.get());
+        core::ptr::drop_in_place((*packet).shared.result.get());
+    }
+}
+
+/// Wait for a spawned thread to complete or be terminated.
+///
+/// Any number of threads may wait, as we put back what we took from the
+/// semaphore.
+///
+/// # Safety
+///
+/// `header` must belong to a spawned thread which has not been freed.
+unsafe fn spawn_wait(header: &SpawnHeader) -> Result<(), Error> {
+    Error::check(unsafe {
+        threadx_sys::_tx_semaphore_get(header.done.get(), threadx_sys::TX_WAIT_FOREVER)
+    })?;
+    Error::check(unsafe { threadx_sys::_tx_semaphore_put(header.done.get()) })
+}
+
+/// Delete a spawned thread which has been waited for, drop whatever is left
+/// in it, and give its memory back to the pool.
+///
+/// # Safety
+///
+/// `header` must belong to a spawned thread which has been waited for with
+/// [`spawn_wait`], and which is not used again.
+unsafe fn spawn_free(header: *mut SpawnHeader) -> Result<(), Error> {
+    // The exit notification stopped the thread being preempted until it had
+    // finished, so it cannot still be running
+    unsafe { (*header).thread.delete() }?;
+    unsafe {
+        threadx_sys::_tx_semaphore_delete((*header).done.get());
+        let memory = (*header).memory;
+        ((*header).drop_contents)(header);
+        BytePool::release(memory)
+    }
+}
+
+/// Permission to wait for a [spawned](spawn) thread to finish.
+///
+/// Dropping the handle without calling [`JoinHandle::join`] detaches the
+/// thread, which carries on running. Once it has finished, its control block
+/// and stack are given back to the byte pool, and its result is dropped, the
+/// next time a thread is spawned or a join handle is dropped. A thread which
+/// was created without auto-start, and never resumed, never finishes.
+pub struct JoinHandle<T> {
+    shared: NonNull<SpawnShared<T>>,
+    _marker: PhantomData<T>,
+}
+
+// Safety: the result is only read by whoever calls `join`, after the thread
+// which wrote it has finished.
+unsafe impl<T: Send> Send for JoinHandle<T> {}
+// Safety: only the thread can be reached through a shared reference
+unsafe impl<T: Send> Sync for JoinHandle<T> {}
+
+impl<T> JoinHandle<T> {
+    fn header(&self) -> &SpawnHeader {
+        unsafe { &(*self.shared.as_ptr()).header }
+    }
+
+    /// Get the spawned thread
+    pub fn thread(&self) -> Pin<&Thread> {
+        // Safety: the thread is not moved or deleted until we are consumed
+        unsafe { Pin::new_unchecked(&self.header().thread) }
+    }
+
+    /// Has the spawned thread completed, or been terminated?
+    pub fn is_finished(&self) -> bool {
+        self.thread().is_finished()
+    }
+
+    /// Wait for the spawned thread to finish, then delete it, give its
+    /// memory back to the byte pool, and return what its closure returned.
+    ///
+    /// Returns [`Error::Terminated`] if the thread was terminated before its
+    /// closure returned, for example because it panicked with
+    /// [panic isolation](crate::panic) enabled.
+    pub fn join(self, _ctx: ThreadContext) -> Result<T, Error> {
+        // We free the thread ourselves, so it must not be detached
+        let this = ManuallyDrop::new(self);
+        // Safety: we own the only handle to the thread
+        unsafe {
+            spawn_wait(this.header())?;
+            let result = (*(*this.shared.as_ptr()).result.get()).take();
+            spawn_free(this.shared.as_ptr() as *mut SpawnHeader)?;
+            result.ok_or(Error::Terminated)
+        }
+    }
+}
+
+impl<T> Drop for JoinHandle<T> {
+    fn drop(&mut self) {
+        let header = self.shared.as_ptr() as *mut SpawnHeader;
+        // Safety: we own the only handle to the thread, so it has not been
+        // freed. If it has not finished, the exit notification puts it on the
+        // list instead.
+        unsafe {
+            if (*header).state.swap(SPAWN_DETACHED, Ordering::AcqRel) == SPAWN_FINISHED {
+                push_spawned(&DETACHED, header);
+            }
+        }
+        if let Some(ctx) = ThreadContext::get() {
+            reap_detached(ctx);
+        }
+    }
+}
+
+impl<T> core::fmt::Debug for JoinHandle<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("JoinHandle")
+            .field("thread", &self.thread())
+            .finish()
+    }
+}
+
+/// Run `f`, which may spawn threads that borrow from the caller's stack.
+///
+/// Before `scope` returns, it waits for every thread spawned in the scope to
+/// finish (including threads spawned by those threads), deletes them, and
+/// gives their memory back to `pool`. Threads not joined with
+/// [`ScopedJoinHandle::join`] have their results dropped.
+///
+/// ```rust,ignore
+/// let mut samples = [0i16; 256];
+/// let (left, right) = samples.split_at_mut(128);
+/// threadx::scope(ctx, pool, |s| {
+///     s.spawn(ctx, c"left", 2048, ThreadOptions::new(Priority::new(5)), || filter(left))?;
+///     s.spawn(ctx, c"right", 2048, ThreadOptions::new(Priority::new(5)), || filter(right))?;
+///     Ok::<_, Error>(())
+/// })?;
+/// ```
+///
+/// Whilst the scope is running, [`Thread::terminate`] and [`Thread::reset`]
+/// refuse to touch the calling thread, as its scoped threads would be left
+/// with borrows of its stack. If the calling thread panics with
+/// [panic isolation](crate::panic) enabled, it waits for its scoped threads
+/// to finish before it is terminated, so they must be able to finish without
+/// it.
+pub fn scope<'env, F, R>(ctx: ThreadContext, pool: Pin<&'static BytePool>, f: F) -> R
+where
+    F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+{
+    let owner = crate::context::current_thread().expect("ThreadContext outside a thread");
+    // Safety: only the owner changes its own list of scopes
+    let head = unsafe { scope_head(owner.as_ptr()) };
+    let scope = Scope {
+        pool,
+        state: ScopeState {
+            threads: AtomicPtr::new(core::ptr::null_mut()),
+            outer: unsafe { head.read_volatile() },
+        },
+        _scope: PhantomData,
+        _env: PhantomData,
+    };
+    // The scope does not move until it has been taken off the list again
+    unsafe { head.write_volatile(&scope.state) };
+    let result = f(&scope);
+    scope.state.join_all(ctx);
+    unsafe { head.write_volatile(scope.state.outer) };
+    result
+}
+
+/// Get a pointer to the innermost [`scope`] a thread is running, which
+/// lives in its control block.
+///
+/// # Safety
+///
+/// `thread` must point to a valid thread control block.
+unsafe fn scope_head(thread: *mut TX_THREAD) -> *mut *const ScopeState {
+    unsafe { core::ptr::addr_of_mut!((*thread).tx_thread_rust_scope) as *mut *const ScopeState }
+}
+
+/// Wait for, and free, the threads spawned in every [`scope`] a thread is
+/// running.
+///
+/// # Safety
+///
+/// `thread` must be the current thread, and must never return to any of the
+/// scopes.
+pub(crate) unsafe fn join_scopes(ctx: ThreadContext, thread: *mut TX_THREAD) {
+    unsafe {
+        let head = scope_head(thread);
+        loop {
+            let state = head.read_volatile();
+            if state.is_null() {
+                break;
+            }
+            (*state).join_all(ctx);
+            head.write_volatile((*state).outer);
+        }
+    }
+}
+
+/// Somewhere to spawn threads which can borrow from outside the scope.
+///
+/// See [`scope`].
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: Pin<&'static BytePool>,
+    state: ScopeState,
+    _scope: PhantomData<&'scope mut &'scope ()>,
+    _env: PhantomData<&'env mut &'env ()>,
+}
+
+/// The part of a [`Scope`] which its owner's control block points to
+struct ScopeState {
+    /// Every thread spawned in the scope, most recent first
+    threads: AtomicPtr<SpawnHeader>,
+    /// The scope the owner was already running, if any
+    outer: *const ScopeState,
+}
+
+// Safety: the list of threads is only changed atomically, and only the owner
+// follows `outer`
+unsafe impl Sync for Scope<'_, '_> {}
+
+impl<'scope> Scope<'scope, '_> {
+    /// Spawn a thread in the scope, allocated from the scope's byte pool.
+    ///
+    /// Otherwise as for [`spawn`]. The scope waits for the thread even if it
+    /// was created without auto-start, so make sure it is resumed.
+    pub fn spawn<F, T>(
+        &'scope self,
+        ctx: ThreadContext,
+        name: &'static CStr,
+        stack_size: usize,
+        options: ThreadOptions,
+        f: F,
+    ) -> Result<ScopedJoinHandle<'scope, T>, Error>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        // Safety: `scope` does not return until the thread has finished and
+        // its result has been taken or dropped.
+        let shared = unsafe { spawn_raw(ctx, self.pool, name, stack_size, options, f) }?;
+        let header = shared.as_ptr() as *mut SpawnHeader;
+        // Safety: nobody else can see this thread's header yet
+        unsafe { push_spawned(&self.state.threads, header) };
+        Ok(ScopedJoinHandle {
+            shared,
+            _scope: PhantomData,
+        })
+    }
+}
+
+impl ScopeState {
+    /// Wait for, and free, every thread spawned in the scope
+    fn join_all(&self, _ctx: ThreadContext) {
+        // Threads we wait for may spawn more, so keep going until none are
+        // left. Nothing is freed until everything has finished, as a thread
+        // may still be joining another.
+        let mut finished: *mut SpawnHeader = core::ptr::null_mut();
+        loop {
+            let mut header = self.threads.swap(core::ptr::null_mut(), Ordering::AcqRel);
+            if header.is_null() {
+                break;
+            }
+            while !header.is_null() {
+                // Safety: each thread in the list is still allocated, and
+                // nobody else follows the list once we have taken it
+                unsafe {
+                    let next = (*header).next;
+                    let _ = spawn_wait(&*header);
+                    (*header).next = finished;
+                    finished = header;
+                    header = next;
+                }
+            }
+        }
+        while !finished.is_null() {
+            // Safety: every thread in the scope has finished, and so has
+            // anything that was joining them
+            unsafe {
+                let next = (*finished).next;
+                let _ = spawn_free(finished);
+                finished = next;
+            }
+        }
+    }
+}
+
+/// Permission to wait for a thread spawned in a [`Scope`].
+pub struct ScopedJoinHandle<'scope, T> {
+    shared: NonNull<SpawnShared<T>>,
+    _scope: PhantomData<&'scope T>,
+}
+
+// Safety: the result is only read by whoever calls `join`, after the thread
+// which wrote it has finished.
+unsafe impl<T: Send> Send for ScopedJoinHandle<'_, T> {}
+// Safety: only the thread can be reached through a shared reference
+unsafe impl<T: Send> Sync for ScopedJoinHandle<'_, T> {}
+
+impl<T> ScopedJoinHandle<'_, T> {
+    fn header(&self) -> &SpawnHeader {
+        unsafe { &(*self.shared.as_ptr()).header }
+    }
+
+    /// Get the spawned thread
+    pub fn thread(&self) -> Pin<&Thread> {
+        // Safety: the thread is not moved or deleted until the scope ends
+        unsafe { Pin::new_unchecked(&self.header().thread) }
+    }
+
+    /// Has the spawned thread completed, or been terminated?
+    pub fn is_finished(&self) -> bool {
+        self.thread().is_finished()
+    }
+
+    /// Wait for the spawned thread to finish, and return what its closure
+    /// returned.
+    ///
+    /// The thread is deleted when the scope ends. Returns
+    /// [`Error::Terminated`] if the thread was terminated before its closure
+    /// returned.
+    pub fn join(self, _ctx: ThreadContext) -> Result<T, Error> {
+        // Safety: the scope frees nothing until we have returned, and we own
+        // the only handle that can take the result
+        unsafe {
+            spawn_wait(self.header())?;
+            (*(*self.shared.as_ptr()).result.get())
+                .take()
+                .ok_or(Error::Terminated)
+        }
+    }
+}
+
+impl<T> core::fmt::Debug for ScopedJoinHandle<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ScopedJoinHandle")
+            .field("thread", &self.thread())
+            .finish()
+    }
+}
+
+/// Reserve a thread and its stack in `.bss`, and create it.
+///
+/// Evaluates to `Result<Pin<&'static Thread>, Error>`. Each use of the macro
+/// reserves its own storage, so if the expansion is executed twice, the second
+/// attempt returns [`Error::ThreadError`].
+///
+/// ```rust,ignore
+/// let thread = threadx::static_thread!(
+///     STACK_SIZE,
+///     c"thread0",
+///     my_thread,
+///     0,
+///     threadx::thread::ThreadOptions::new(threadx::thread::Priority::new(1))
+/// )?;
+/// ```
+#[macro_export]
+macro_rules! static_thread {
+    ($stack_size:expr, $name:expr, $entry:expr, $input:expr, $options:expr) => {{
+        static THREAD: $crate::thread::StaticThread<{ $stack_size }> =
+            $crate::thread::StaticThread::new();
+        THREAD.create($name, $entry, $input, $options)
+    }};
+}
+
+// End of file
@@ -0,0 +1,217 @@
+//! Time
+//!
+//! ThreadX counts time in ticks of its timer interrupt, which are usually
+//! 10ms apart - see [`ticks`]. For anything finer, the application can give
+//! us a [`Clock`], which is a free-running counter extended to 64 bits.
+//! [`Instant`]s are then taken from that clock.
+//!
+//! ```rust,ignore
+//! threadx::time::set_clock(&MY_CLOCK).expect("Clock already set");
+//! let start = threadx::time::Instant::now().unwrap();
+//! do_something();
+//! defmt::info!("That took {=u64:us}", start.elapsed().as_micros() as u64);
+//! ```
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicU8, Ordering},
+    time::Duration,
+};
+
+use crate::Error;
+
+/// A high-resolution clock, which never goes backwards.
+pub trait Clock: Sync {
+    /// How many times a second the clock counts
+    fn frequency_hz(&self) -> u32;
+
+    /// How many counts there have been since the clock started.
+    ///
+    /// This must be callable from any context, including interrupts.
+    fn now(&self) -> u64;
+}
+
+/// Nobody has set the clock
+const UNSET: u8 = 0;
+/// The clock is being set
+const SETTING: u8 = 1;
+/// The clock has been set, and cannot change
+const SET: u8 = 2;
+
+/// Holds the clock
+struct ClockCell(UnsafeCell<Option<&'static dyn Clock>>);
+
+// Safety: the cell is written once, whilst STATE is SETTING, and only read
+// once STATE is SET.
+unsafe impl Sync for ClockCell {}
+
+static CLOCK: ClockCell = ClockCell(UnsafeCell::new(None));
+
+static STATE: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Set the clock that [`Instant`]s are taken from.
+///
+/// This can only be done once. Returns `CallerError` if the clock has
+/// already been set.
+pub fn set_clock(clock: &'static dyn Clock) -> Result<(), Error> {
+    STATE
+        .compare_exchange(UNSET, SETTING, Ordering::Acquire, Ordering::Relaxed)
+        .map_err(|_| Error::CallerError)?;
+    // Safety: only we can be in here, and nobody reads until STATE is SET
+    unsafe { *CLOCK.0.get() = Some(clock) };
+    STATE.store(SET, Ordering::Release);
+    Ok(())
+}
+
+/// Get the clock, if one has been set.
+pub fn clock() -> Option<&'static dyn Clock> {
+    if STATE.load(Ordering::Acquire) != SET {
+        return None;
+    }
+    // Safety: the cell does not change once STATE is SET
+    unsafe { *CLOCK.0.get() }
+}
+
+/// Get the ThreadX time, in timer ticks.
+///
+/// This wraps around after 2^32 ticks.
+pub fn ticks() -> u32 {
+    unsafe { threadx_sys::_tx_time_get() as u32 }
+}
+
+/// A moment in time, as measured by the [`Clock`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Instant {
+    /// Microseconds since the clock started
+    micros: u64,
+}
+
+impl Instant {
+    /// Read the clock. Returns `None` if no clock has been set, or if the
+    /// clock says it runs at 0 Hz.
+    pub fn now() -> Option<Instant> {
+        let clock = clock()?;
+        Instant::from_counts(clock.now(), clock.frequency_hz())
+    }
+
+    /// Convert a count from a clock running at `frequency_hz`.
+    ///
+    /// Returns `None` if `frequency_hz` is zero, or if the count is too
+    /// large to fit in microseconds.
+    pub const fn from_counts(counts: u64, frequency_hz: u32) -> Option<Instant> {
+        let hz = frequency_hz as u64;
+        if hz == 0 {
+            return None;
+        }
+        // Split the count up, so that the remainder part cannot overflow
+        let Some(whole) = (counts / hz).checked_mul(1_000_000) else {
+            return None;
+        };
+        let Some(micros) = whole.checked_add((counts % hz) * 1_000_000 / hz) else {
+            return None;
+        };
+        Some(Instant { micros })
+    }
+
+    /// Make an instant from microseconds since the clock started
+    pub const fn from_micros(micros: u64) -> Instant {
+        Instant { micros }
+    }
+
+    /// Get how many microseconds after the clock started this was
+    pub const fn as_micros(self) -> u64 {
+        self.micros
+    }
+
+    /// Get how long after `earlier` this was, or zero if it was before
+    pub fn duration_since(self, earlier: Instant) -> Duration {
+        self.saturating_duration_since(earlier)
+    }
+
+    /// Get how long after `earlier` this was, or `None` if it was before
+    pub fn checked_duration_since(self, earlier: Instant) -> Option<Duration> {
+        self.micros
+            .checked_sub(earlier.micros)
+            .map(Duration::from_micros)
+    }
+
+    /// Get how long after `earlier` this was, or zero if it was before
+    pub fn saturating_duration_since(self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier).unwrap_or_default()
+    }
+
+    /// Get the instant `duration` after this one, or `None` if that cannot
+    /// be represented. Anything finer than a microsecond is dropped.
+    pub fn checked_add(self, duration: Duration) -> Option<Instant> {
+        let micros = u64::try_from(duration.as_micros()).ok()?;
+        Some(Instant {
+            micros: self.micros.checked_add(micros)?,
+        })
+    }
+
+    /// Get the instant `duration` before this one, or `None` if that would
+    /// be before the clock started. Anything finer than a microsecond is
+    /// dropped.
+    pub fn checked_sub(self, duration: Duration) -> Option<Instant> {
+        let micros = u64::try_from(duration.as_micros()).ok()?;
+        Some(Instant {
+            micros: self.micros.checked_sub(micros)?,
+        })
+    }
+
+    /// Get how long ago this was
+    pub fn elapsed(self) -> Duration {
+        Instant::now().unwrap_or(self).duration_since(self)
+    }
+}
+
+impl core::ops::Add<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    ///
+    /// If the result cannot be represented. See [`Instant::checked_add`].
+    fn add(self, rhs: Duration) -> Instant {
+        self.checked_add(rhs)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl core::ops::AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl core::ops::Sub<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    ///
+    /// If the result would be before the clock started. See
+    /// [`Instant::checked_sub`].
+    fn sub(self, rhs: Duration) -> Instant {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl core::ops::SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, rhs: Duration) {
+        *self = *self - rhs;
+    }
+}
+
+impl core::ops::Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+// End of file
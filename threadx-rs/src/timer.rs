@@ -0,0 +1,157 @@
+//! ThreadX application timers
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    cell::UnsafeCell,
+    ffi::CStr,
+    marker::PhantomPinned,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use threadx_sys::{TX_TIMER, ULONG};
+
+use crate::Error;
+
+/// The function called when a timer expires
+pub type TimerExpiry = extern "C" fn(ULONG);
+
+/// A ThreadX application timer
+#[repr(transparent)]
+pub struct Timer {
+    inner: UnsafeCell<TX_TIMER>,
+    _pin: PhantomPinned,
+}
+
+// Safety: every access to the inner control block goes via a ThreadX API,
+// which handles the locking.
+unsafe impl Sync for Timer {}
+
+impl Timer {
+    /// Create an empty (all-zero) control block.
+    const fn zeroed() -> Timer {
+        Timer {
+            // Safety: TX_TIMER is plain-old-data, and all zeroes is a valid
+            // value for every field.
+            inner: UnsafeCell::new(unsafe { core::mem::zeroed() }),
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Get a raw pointer to the ThreadX control block
+    pub fn as_ptr(&self) -> *mut TX_TIMER {
+        self.inner.get()
+    }
+
+    /// Start the timer
+    pub fn activate(&self) -> Result<(), Error> {
+        Error::check(unsafe { threadx_sys::_tx_timer_activate(self.as_ptr()) })
+    }
+
+    /// Stop the timer
+    pub fn deactivate(&self) -> Result<(), Error> {
+        Error::check(unsafe { threadx_sys::_tx_timer_deactivate(self.as_ptr()) })
+    }
+
+    /// Change the expiry settings of a deactivated timer.
+    ///
+    /// A `reschedule_ticks` of zero makes this a one-shot timer.
+    pub fn change(&self, initial_ticks: u32, reschedule_ticks: u32) -> Result<(), Error> {
+        Error::check(unsafe {
+            threadx_sys::_tx_timer_change(
+                self.as_ptr(),
+                initial_ticks as ULONG,
+                reschedule_ticks as ULONG,
+            )
+        })
+    }
+}
+
+/// Storage for a timer, suitable for placing in a `static`.
+///
+/// Use the [`static_timer!`](crate::static_timer) macro rather than using this
+/// type directly.
+pub struct StaticTimer {
+    timer: Timer,
+    created: AtomicBool,
+}
+
+impl StaticTimer {
+    /// Reserve space for a timer. It ends up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticTimer {
+        StaticTimer {
+            timer: Timer::zeroed(),
+            created: AtomicBool::new(false),
+        }
+    }
+
+    /// Create the ThreadX timer.
+    ///
+    /// The `expiry` function is called with `input` from the ThreadX timer
+    /// thread (or from the timer interrupt, if ThreadX was built with
+    /// `TX_TIMER_PROCESS_IN_ISR`), so it must not block. A `reschedule_ticks`
+    /// of zero makes this a one-shot timer.
+    ///
+    /// Returns [`Error::TimerError`] if the timer has already been created.
+    pub fn create(
+        &'static self,
+        name: &'static CStr,
+        expiry: TimerExpiry,
+        input: ULONG,
+        initial_ticks: u32,
+        reschedule_ticks: u32,
+        auto_activate: bool,
+    ) -> Result<Pin<&'static Timer>, Error> {
+        if self.created.swap(true, Ordering::AcqRel) {
+            return Err(Error::TimerError);
+        }
+        let res = unsafe {
+            threadx_sys::_tx_timer_create(
+                self.timer.as_ptr(),
+                crate::name_ptr(name),
+                Some(expiry as unsafe extern "C" fn(ULONG)),
+                input,
+                initial_ticks as ULONG,
+                reschedule_ticks as ULONG,
+                if auto_activate {
+                    threadx_sys::TX_AUTO_ACTIVATE
+                } else {
+                    threadx_sys::TX_NO_ACTIVATE
+                },
+            )
+        };
+        if let Err(e) = Error::check(res) {
+            self.created.store(false, Ordering::Release);
+            return Err(e);
+        }
+        Ok(Pin::static_ref(&self.timer))
+    }
+}
+
+/// Reserve a timer in `.bss`, and create it in the active state.
+///
+/// Evaluates to `Result<Pin<&'static Timer>, Error>`.
+///
+/// ```rust,ignore
+/// // Call `tick(0)` after 10 ticks, and every 100 ticks after that
+/// let timer = threadx::static_timer!(c"timer0", tick, 0, 10, 100)?;
+/// ```
+#[macro_export]
+macro_rules! static_timer {
+    ($name:expr, $expiry:expr, $input:expr, $initial_ticks:expr, $reschedule_ticks:expr) => {{
+        static TIMER: $crate::timer::StaticTimer = $crate::timer::StaticTimer::new();
+        TIMER.create(
+            $name,
+            $expiry,
+            $input,
+            $initial_ticks,
+            $reschedule_ticks,
+            true,
+        )
+    }};
+}
+
+// End of file
@@ -0,0 +1,255 @@
+//! Thread-local storage
+//!
+//! Every ThreadX thread control block has a `tx_thread_rust_tls` field, added
+//! through the `TX_THREAD_USER_EXTENSION` in the `tx_user.h` that
+//! `threadx-sys` generates. It points to a linked list holding one value for
+//! each [`LocalKey`] the thread has used.
+//!
+//! Values are created the first time a thread uses a key, in memory allocated
+//! from the byte pool given to [`init`]. They are dropped when the thread is
+//! deleted with [`Thread::delete`](crate::thread::Thread::delete), or reset
+//! with [`Thread::reset`](crate::thread::Thread::reset). That happens on the
+//! deleting thread, not on the thread that owned them, so values must be
+//! `Send`.
+//!
+//! ```rust,ignore
+//! threadx::thread_local! {
+//!     static COUNTER: core::cell::Cell<u32> = core::cell::Cell::new(0);
+//! }
+//!
+//! COUNTER.with(|c| c.set(c.get() + 1));
+//! ```
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    pin::Pin,
+    ptr::NonNull,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use threadx_sys::TX_THREAD;
+
+use crate::{byte_pool::BytePool, context::ThreadContext, Error, Wait};
+
+/// The pool we allocate thread-local values from
+static POOL: AtomicPtr<BytePool> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Set the byte pool that thread-local values are allocated from.
+///
+/// Must be called before any thread uses a [`LocalKey`].
+pub fn init(pool: Pin<&'static BytePool>) {
+    POOL.store(
+        pool.get_ref() as *const BytePool as *mut BytePool,
+        Ordering::Release,
+    );
+}
+
+/// The type-erased part of each entry in a thread's list of values
+#[repr(C)]
+struct Header {
+    /// The next entry in the list
+    next: *mut Header,
+    /// The address of the `LocalKey` this value belongs to
+    key: *const (),
+    /// What we got back from the byte pool (which may be before the header,
+    /// if we had to align it)
+    allocation: NonNull<u8>,
+    /// Drops the value in this entry
+    drop_value: unsafe fn(*mut Header),
+}
+
+/// An entry in a thread's list of values
+#[repr(C)]
+struct Node<T> {
+    header: Header,
+    value: T,
+}
+
+/// Drop the value held in a `Node<T>`
+///
+/// # Safety
+///
+/// `header` must point to the header of a `Node<T>`, and the value must not be
+/// used again.
+unsafe fn drop_value<T>(header: *mut Header) {
+    let node = header as *mut Node<T>;
+    unsafe { core::ptr::drop_in_place(core::ptr::addr_of_mut!((*node).value)) };
+}
+
+/// Get a pointer to the head of a thread's list of values
+///
+/// # Safety
+///
+/// `thread` must point to a valid thread control block.
+unsafe fn list_head(thread: *mut TX_THREAD) -> *mut *mut Header {
+    unsafe { core::ptr::addr_of_mut!((*thread).tx_thread_rust_tls) as *mut *mut Header }
+}
+
+/// A key for accessing a thread-local value.
+///
+/// Create these with the [`thread_local!`](crate::thread_local) macro.
+pub struct LocalKey<T: Send + 'static> {
+    init: fn() -> T,
+}
+
+impl<T: Send + 'static> LocalKey<T> {
+    #[doc(hidden)]
+    pub const fn new(init: fn() -> T) -> LocalKey<T> {
+        LocalKey { init }
+    }
+
+    /// Get a reference to this thread's value, and pass it to `f`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a thread, or if the value cannot be
+    /// allocated. See [`LocalKey::try_with`].
+    pub fn with<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        match self.try_with(f) {
+            Ok(r) => r,
+            Err(e) => panic!("Cannot access thread-local value: {:?}", e),
+        }
+    }
+
+    /// Get a reference to this thread's value, and pass it to `f`.
+    ///
+    /// Returns [`Error::CallerError`] if called during initialisation or from
+    /// an interrupt, [`Error::PoolError`] if [`init`] has not been called, and
+    /// [`Error::NoMemory`] if the pool is full.
+    pub fn try_with<F, R>(&'static self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let thread = crate::context::current_thread().ok_or(Error::CallerError)?;
+        // Safety: only this thread ever modifies its own list (until it is
+        // deleted, at which point it is no longer running).
+        unsafe {
+            let head = list_head(thread.as_ptr());
+            if let Some(value) = self.find(head) {
+                return Ok(f(&*value));
+            }
+            // The initialiser may use other keys (or even this one), which
+            // adds to the list, so only look at the head once it has run
+            let value = (self.init)();
+            if let Some(existing) = self.find(head) {
+                drop(value);
+                return Ok(f(&*existing));
+            }
+            let (node, allocation) = self.allocate()?;
+            node.as_ptr().write(Node {
+                header: Header {
+                    next: *head,
+                    key: self.key(),
+                    allocation,
+                    drop_value: drop_value::<T>,
+                },
+                value,
+            });
+            // Nodes never move once they are in the list, so the reference
+            // we give out stays valid even if `f` uses other keys.
+            *head = node.as_ptr() as *mut Header;
+            Ok(f(&(*node.as_ptr()).value))
+        }
+    }
+
+    /// Identifies this key in a thread's list of values
+    fn key(&'static self) -> *const () {
+        self as *const LocalKey<T> as *const ()
+    }
+
+    /// Find this key's value in a thread's list of values.
+    ///
+    /// # Safety
+    ///
+    /// `head` must be the head of the current thread's list.
+    unsafe fn find(&'static self, head: *mut *mut Header) -> Option<*const T> {
+        let key = self.key();
+        let mut entry = unsafe { *head };
+        while !entry.is_null() {
+            // Safety: every entry in the list is a live node
+            unsafe {
+                if (*entry).key == key {
+                    return Some(core::ptr::addr_of!((*(entry as *const Node<T>)).value));
+                }
+                entry = (*entry).next;
+            }
+        }
+        None
+    }
+
+    /// Allocate memory for a new node, suitably aligned.
+    ///
+    /// Returns the uninitialised node, and the allocation it lives in.
+    fn allocate(&self) -> Result<(NonNull<Node<T>>, NonNull<u8>), Error> {
+        let pool = POOL.load(Ordering::Acquire);
+        if pool.is_null() {
+            return Err(Error::PoolError);
+        }
+        // Safety: we were given a `&'static BytePool`
+        let pool = unsafe { &*pool };
+        let align = core::mem::align_of::<Node<T>>();
+        let size = core::mem::size_of::<Node<T>>() + align - 1;
+        // Safety: `try_with` checked that we are in a thread
+        let ctx = unsafe { ThreadContext::new_unchecked() };
+        let allocation = pool.allocate(ctx, size, Wait::NONE)?;
+        let offset = allocation.as_ptr().align_offset(align);
+        // Safety: we allocated enough extra space for the alignment
+        let node = unsafe { allocation.as_ptr().add(offset) as *mut Node<T> };
+        Ok((unsafe { NonNull::new_unchecked(node) }, allocation))
+    }
+}
+
+/// Drop all the thread-local values belonging to a thread.
+///
+/// # Safety
+///
+/// `thread` must point to a valid thread control block, for a thread which
+/// will never run again.
+pub(crate) unsafe fn destroy(thread: *mut TX_THREAD) {
+    unsafe {
+        let mut entry = list_head(thread).replace(core::ptr::null_mut());
+        while !entry.is_null() {
+            let next = (*entry).next;
+            let allocation = (*entry).allocation;
+            ((*entry).drop_value)(entry);
+            let _ = BytePool::release(allocation);
+            entry = next;
+        }
+    }
+}
+
+/// Declare one or more thread-local values.
+///
+/// Works like `std::thread_local!`, and creates a `static` [`LocalKey`] for
+/// each value. The initialiser runs the first time each thread uses the key.
+///
+/// ```rust,ignore
+/// threadx::thread_local! {
+///     /// Per-thread logging context
+///     static CONTEXT: RefCell<Context> = RefCell::new(Context::new());
+/// }
+/// ```
+#[macro_export]
+macro_rules! thread_local {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr; $($rest:tt)*) => {
+        $crate::thread_local!($(#[$attr])* $vis static $name: $t = $init);
+        $crate::thread_local!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr) => {
+        $(#[$attr])*
+        $vis static $name: $crate::tls::LocalKey<$t> = $crate::tls::LocalKey::new({
+            fn init() -> $t {
+                $init
+            }
+            init
+        });
+    };
+}
+
+// End of file
@@ -0,0 +1,414 @@
+//! A software watchdog for ThreadX threads
+//!
+//! Each thread that wants watching is registered with a deadline, in timer
+//! ticks, and must then [`check_in`](Registration::check_in) at least that
+//! often. A watchdog thread calls [`StaticWatchdog::run`], which looks at every
+//! registered thread each period. When a thread misses its deadline, the
+//! watchdog works out why from the thread's state and run count, reports it,
+//! and then takes the [`Action`] the thread was registered with.
+//!
+//! ```rust,ignore
+//! static WATCHDOG: StaticWatchdog<4> = StaticWatchdog::new();
+//!
+//! extern "C" fn watchdog_thread(_input: u32) {
+//!     let ctx = ThreadContext::get().expect("Not in a thread");
+//!     // Look every 50 ticks, feeding the hardware watchdog whilst all is well
+//!     WATCHDOG.run(ctx, 50, || hw_watchdog_feed(), |expiry| {
+//!         defmt::warn!("{}", expiry);
+//!     });
+//! }
+//!
+//! extern "C" fn my_thread(_input: u32) {
+//!     let me = threadx::thread::current().expect("Not in a thread");
+//!     let watch = WATCHDOG.register(me, 200, Action::Log).expect("Watchdog full");
+//!     loop {
+//!         do_some_work();
+//!         watch.check_in();
+//!     }
+//! }
+//! ```
+//!
+//! The watchdog thread should have a higher priority than every thread it
+//! watches, or a busy thread can stop it from looking. Its period should be
+//! at most half the shortest deadline, so that it can tell a starved thread
+//! from one which is running but stuck.
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    pin::Pin,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU8, Ordering},
+};
+
+use threadx_sys::TX_THREAD;
+
+use crate::{
+    context::ThreadContext,
+    thread::{Thread, ThreadState},
+    Error,
+};
+
+/// The slot is not in use
+const FREE: u8 = 0;
+/// The slot is being filled in by `register`
+const CLAIMED: u8 = 1;
+/// The slot is watching a thread
+const ACTIVE: u8 = 2;
+
+/// What the watchdog does when a thread misses its deadline
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Action {
+    /// Report it, and carry on watching
+    Log,
+    /// Terminate, reset and resume the thread, and give it a fresh deadline.
+    ///
+    /// As terminating a thread is `unsafe`, this action can only be chosen
+    /// with [`StaticWatchdog::register_restartable`].
+    Restart,
+    /// Stop feeding the hardware watchdog, so that it resets the system
+    Escalate,
+    /// Panic on the watchdog thread
+    ///
+    /// If [panic isolation](crate::panic) is enabled, that only stops the
+    /// watchdog thread.
+    Panic,
+}
+
+impl Action {
+    /// Convert back from the value stored in a slot
+    const fn from_raw(raw: u8) -> Action {
+        match raw {
+            0 => Action::Log,
+            1 => Action::Restart,
+            2 => Action::Escalate,
+            _ => Action::Panic,
+        }
+    }
+}
+
+/// Why the watchdog thinks a thread missed its deadline
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Cause {
+    /// Ready to run, but not scheduled since the watchdog last looked - it is
+    /// being starved by threads of higher priority
+    Starved,
+    /// Ready to run, and being scheduled, but not checking in - it is probably
+    /// stuck in a loop
+    Looping,
+    /// Waiting for something (a mutex, a queue, etc), or sleeping, for longer
+    /// than its deadline
+    Blocked(ThreadState),
+    /// Suspended, returned from its entry function, or terminated
+    Stopped(ThreadState),
+}
+
+impl Cause {
+    /// Work out the cause from the thread's state, and whether it has been
+    /// scheduled since the watchdog last looked.
+    fn diagnose(state: ThreadState, scheduled: bool) -> Cause {
+        match state {
+            ThreadState::Ready if scheduled => Cause::Looping,
+            ThreadState::Ready => Cause::Starved,
+            ThreadState::Suspended | ThreadState::Completed | ThreadState::Terminated => {
+                Cause::Stopped(state)
+            }
+            other => Cause::Blocked(other),
+        }
+    }
+}
+
+/// Details of a missed deadline
+#[derive(Debug, Copy, Clone)]
+pub struct Expiry {
+    /// The thread which missed its deadline
+    pub thread: Pin<&'static Thread>,
+    /// Ticks since the thread last checked in
+    pub overdue: u32,
+    /// How many times the thread has been scheduled, from `tx_thread_info_get`
+    pub run_count: u32,
+    /// Why we think it missed its deadline
+    pub cause: Cause,
+    /// What the watchdog is about to do about it
+    pub action: Action,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Expiry {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Thread {=str} has not checked in for {=u32} ticks ({}, run count {=u32}). Action: {}",
+            self.thread.display_name(),
+            self.overdue,
+            self.cause,
+            self.run_count,
+            self.action
+        )
+    }
+}
+
+/// One watched thread
+struct Slot {
+    state: AtomicU8,
+    thread: AtomicPtr<TX_THREAD>,
+    deadline: AtomicU32,
+    action: AtomicU8,
+    /// When the thread last checked in
+    last_check_in: AtomicU32,
+    /// The thread's run count when the watchdog last looked
+    run_count: AtomicU32,
+    /// Set once the current miss has been dealt with, so we only act once
+    expired: AtomicBool,
+}
+
+impl Slot {
+    const fn new() -> Slot {
+        Slot {
+            state: AtomicU8::new(FREE),
+            thread: AtomicPtr::new(core::ptr::null_mut()),
+            deadline: AtomicU32::new(0),
+            action: AtomicU8::new(Action::Log as u8),
+            last_check_in: AtomicU32::new(0),
+            run_count: AtomicU32::new(0),
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    /// Look at the thread in this slot, returning a report if it is overdue
+    /// and we have not already reported it.
+    fn check(&self, now: u32) -> Option<Expiry> {
+        if self.state.load(Ordering::Acquire) != ACTIVE {
+            return None;
+        }
+        let ptr = self.thread.load(Ordering::Relaxed);
+        // Safety: `register` only stores pointers to static threads
+        let thread = unsafe { Pin::new_unchecked(&*(ptr as *const Thread)) };
+        let (state, run_count) = thread_info(ptr);
+        let scheduled = self.run_count.swap(run_count, Ordering::Relaxed) != run_count;
+        let overdue = now.wrapping_sub(self.last_check_in.load(Ordering::Acquire));
+        if overdue <= self.deadline.load(Ordering::Relaxed)
+            || self.expired.swap(true, Ordering::AcqRel)
+        {
+            return None;
+        }
+        Some(Expiry {
+            thread,
+            overdue,
+            run_count,
+            cause: Cause::diagnose(state, scheduled),
+            action: Action::from_raw(self.action.load(Ordering::Relaxed)),
+        })
+    }
+
+    /// Start a fresh deadline
+    fn check_in(&self) {
+        self.last_check_in.store(now(), Ordering::Release);
+        self.expired.store(false, Ordering::Release);
+    }
+}
+
+/// A thread's registration with a watchdog
+#[derive(Copy, Clone)]
+pub struct Registration {
+    slot: &'static Slot,
+}
+
+impl Registration {
+    /// Tell the watchdog that the thread is still making progress.
+    ///
+    /// This is cheap, never blocks, and may be called from anywhere, although
+    /// it normally only makes sense on the thread being watched.
+    pub fn check_in(&self) {
+        self.slot.check_in();
+    }
+
+    /// Stop watching the thread.
+    pub fn unregister(self) {
+        self.slot.state.store(FREE, Ordering::Release);
+    }
+}
+
+/// Storage for a watchdog which can watch up to `N` threads, suitable for
+/// placing in a `static`.
+pub struct StaticWatchdog<const N: usize> {
+    slots: [Slot; N],
+    escalated: AtomicBool,
+}
+
+impl<const N: usize> StaticWatchdog<N> {
+    /// Reserve space for a watchdog. It ends up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticWatchdog<N> {
+        StaticWatchdog {
+            slots: [const { Slot::new() }; N],
+            escalated: AtomicBool::new(false),
+        }
+    }
+
+    /// Start watching a thread, which must then check in at least every
+    /// `deadline` ticks, starting from now.
+    ///
+    /// If the thread is already being watched, its deadline and action are
+    /// updated instead, so a thread can safely register itself each time it
+    /// is (re)started.
+    ///
+    /// Returns [`Error::NoMemory`] if the watchdog is already watching `N`
+    /// threads, and [`Error::CallerError`] if `action` is
+    /// [`Action::Restart`].
+    pub fn register(
+        &'static self,
+        thread: Pin<&'static Thread>,
+        deadline: u32,
+        action: Action,
+    ) -> Result<Registration, Error> {
+        if action == Action::Restart {
+            return Err(Error::CallerError);
+        }
+        self.register_action(thread, deadline, action)
+    }
+
+    /// Start watching a thread, which is terminated, reset and resumed if
+    /// it misses its deadline. Otherwise as for [`register`](Self::register).
+    ///
+    /// # Safety
+    ///
+    /// The requirements of [`Thread::terminate`] and [`Thread::reset`] must
+    /// hold whenever the thread might miss its deadline.
+    pub unsafe fn register_restartable(
+        &'static self,
+        thread: Pin<&'static Thread>,
+        deadline: u32,
+    ) -> Result<Registration, Error> {
+        self.register_action(thread, deadline, Action::Restart)
+    }
+
+    fn register_action(
+        &'static self,
+        thread: Pin<&'static Thread>,
+        deadline: u32,
+        action: Action,
+    ) -> Result<Registration, Error> {
+        let existing = self.slots.iter().find(|slot| {
+            slot.thread.load(Ordering::Relaxed) == thread.as_ptr()
+                && slot
+                    .state
+                    .compare_exchange(ACTIVE, CLAIMED, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+        });
+        let slot = existing
+            .or_else(|| {
+                self.slots.iter().find(|slot| {
+                    slot.state
+                        .compare_exchange(FREE, CLAIMED, Ordering::Acquire, Ordering::Relaxed)
+                        .is_ok()
+                })
+            })
+            .ok_or(Error::NoMemory)?;
+        slot.thread.store(thread.as_ptr(), Ordering::Relaxed);
+        slot.deadline.store(deadline, Ordering::Relaxed);
+        slot.action.store(action as u8, Ordering::Relaxed);
+        slot.run_count
+            .store(thread_info(thread.as_ptr()).1, Ordering::Relaxed);
+        slot.check_in();
+        slot.state.store(ACTIVE, Ordering::Release);
+        Ok(Registration { slot })
+    }
+
+    /// Look at every registered thread once.
+    ///
+    /// `on_expiry` is told about each thread that has missed its deadline
+    /// since it last checked in, just before its [`Action`] is taken. Returns
+    /// `false` if any thread has ever missed a deadline with
+    /// [`Action::Escalate`].
+    pub fn check(&'static self, _ctx: ThreadContext, mut on_expiry: impl FnMut(&Expiry)) -> bool {
+        let now = now();
+        for slot in &self.slots {
+            let Some(expiry) = slot.check(now) else {
+                continue;
+            };
+            #[cfg(feature = "defmt")]
+            defmt::warn!("Watchdog: {}", expiry);
+            on_expiry(&expiry);
+            match expiry.action {
+                Action::Log => {}
+                Action::Restart => {
+                    let thread = expiry.thread;
+                    // Safety: whoever registered the thread with this action
+                    // promised this is fine
+                    let restarted = unsafe {
+                        let _ = thread.terminate();
+                        thread.reset()
+                    }
+                    .and_then(|_| thread.resume());
+                    if restarted.is_ok() {
+                        slot.check_in();
+                    }
+                }
+                Action::Escalate => self.escalated.store(true, Ordering::Release),
+                Action::Panic => panic!(
+                    "Thread {} missed its watchdog deadline",
+                    expiry.thread.display_name()
+                ),
+            }
+        }
+        !self.escalated.load(Ordering::Acquire)
+    }
+
+    /// Look at every registered thread every `period` ticks, forever.
+    ///
+    /// Call this from the entry function of the watchdog thread. `feed` is
+    /// called after each look, unless a thread has missed a deadline with
+    /// [`Action::Escalate`], after which it is never called again. Use it to
+    /// feed a hardware watchdog. See [`check`](Self::check) for `on_expiry`.
+    pub fn run(
+        &'static self,
+        ctx: ThreadContext,
+        period: u32,
+        mut feed: impl FnMut(),
+        mut on_expiry: impl FnMut(&Expiry),
+    ) -> ! {
+        loop {
+            if self.check(ctx, &mut on_expiry) {
+                feed();
+            }
+            let _ = crate::thread::sleep(ctx, period);
+        }
+    }
+}
+
+/// The ThreadX tick count
+fn now() -> u32 {
+    unsafe { threadx_sys::_tx_time_get() as u32 }
+}
+
+/// Get the state and run count of a thread
+fn thread_info(ptr: *mut TX_THREAD) -> (ThreadState, u32) {
+    let mut name = core::ptr::null_mut();
+    let mut state = 0;
+    let mut run_count = 0;
+    let mut priority = 0;
+    let mut preemption_threshold = 0;
+    let mut time_slice = 0;
+    let mut next = core::ptr::null_mut();
+    let mut next_suspended = core::ptr::null_mut();
+    unsafe {
+        threadx_sys::_tx_thread_info_get(
+            ptr,
+            &mut name,
+            &mut state,
+            &mut run_count,
+            &mut priority,
+            &mut preemption_threshold,
+            &mut time_slice,
+            &mut next,
+            &mut next_suspended,
+        );
+    }
+    (ThreadState::from_raw(state), run_count as u32)
+}
+
+// End of file
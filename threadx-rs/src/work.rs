@@ -0,0 +1,202 @@
+//! Deferred work, for doing the slow part of interrupt handling in a thread
+//!
+//! An interrupt handler should do as little as it can. It can
+//! [`submit`](WorkQueue::submit) the rest to a work queue, which never
+//! blocks, and one or more worker threads then run it, at whatever priority
+//! those threads were given. If the queue is full, the work is dropped and
+//! counted as an overrun.
+//!
+//! ```rust,ignore
+//! static WORK: StaticWorkQueue<{ threadx::work::words(16) }> = StaticWorkQueue::new();
+//!
+//! extern "C" fn worker(_input: u32) {
+//!     let ctx = ThreadContext::get().expect("Not in a thread");
+//!     WORK.get().expect("Work queue not created").run(ctx);
+//! }
+//!
+//! fn on_rx(_ctx: ThreadContext, byte: usize) {
+//!     defmt::info!("Got {=u8}", byte as u8);
+//! }
+//!
+//! // In the interrupt handler
+//! if let Some(work) = WORK.get() {
+//!     let _ = work.submit(isr_ctx, on_rx, byte as usize);
+//! }
+//! ```
+
+// SPDX-FileCopyrightText: Copyright (c) 2026 Ferrous Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::{
+    ffi::CStr,
+    pin::Pin,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::{
+    context::{Context, ThreadContext},
+    queue::{message_words, Queue, StaticQueue},
+    Error, Wait,
+};
+
+/// A piece of deferred work, which is given the word it was submitted with
+pub type WorkFn = fn(ThreadContext, usize);
+
+/// What we put in the queue
+#[derive(Copy, Clone)]
+struct WorkItem {
+    func: WorkFn,
+    arg: usize,
+}
+
+/// Work out how many ThreadX words a work queue holding `capacity` items
+/// needs.
+pub const fn words(capacity: usize) -> usize {
+    capacity * message_words::<WorkItem>()
+}
+
+/// The running totals for a work queue
+struct Counters {
+    submitted: AtomicU32,
+    completed: AtomicU32,
+    overruns: AtomicU32,
+    peak_depth: AtomicU32,
+}
+
+/// Statistics for a work queue
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WorkStats {
+    /// Items added to the queue
+    pub submitted: u32,
+    /// Items a worker has run
+    pub completed: u32,
+    /// Items dropped because the queue was full
+    pub overruns: u32,
+    /// Items in the queue right now
+    pub depth: u32,
+    /// The most items there have been in the queue at once
+    pub peak_depth: u32,
+    /// The most items the queue can hold
+    pub capacity: u32,
+}
+
+/// A handle to a work queue
+#[derive(Copy, Clone)]
+pub struct WorkQueue {
+    queue: Pin<&'static Queue<WorkItem>>,
+    counters: &'static Counters,
+}
+
+impl WorkQueue {
+    /// Add some work to the queue, for a worker to run with `arg`.
+    ///
+    /// Never blocks. If the queue is full, the work is dropped, counted as an
+    /// overrun, and we return [`Error::QueueFull`].
+    pub fn submit(&self, ctx: impl Context, func: WorkFn, arg: usize) -> Result<(), Error> {
+        // Count the item before a worker can possibly have run it
+        self.counters.submitted.fetch_add(1, Ordering::Relaxed);
+        let res = crate::context::without_interrupts(|| {
+            // A worker waiting on the queue is handed the item directly
+            let depth = if self.waiting() > 0 {
+                0
+            } else {
+                self.depth() + 1
+            };
+            self.queue.try_send(ctx, WorkItem { func, arg })?;
+            self.counters.peak_depth.fetch_max(depth, Ordering::Relaxed);
+            Ok(())
+        });
+        if let Err(e) = res {
+            self.counters.submitted.fetch_sub(1, Ordering::Relaxed);
+            if e == Error::QueueFull {
+                self.counters.overruns.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        res
+    }
+
+    /// Run work from the queue, forever.
+    ///
+    /// Call this from the entry function of each worker thread.
+    pub fn run(&self, ctx: ThreadContext) -> ! {
+        loop {
+            if let Ok(item) = self.queue.receive(ctx, Wait::FOREVER) {
+                (item.func)(ctx, item.arg);
+                self.counters.completed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Get the statistics for the queue
+    pub fn stats(&self) -> WorkStats {
+        WorkStats {
+            submitted: self.counters.submitted.load(Ordering::Relaxed),
+            completed: self.counters.completed.load(Ordering::Relaxed),
+            overruns: self.counters.overruns.load(Ordering::Relaxed),
+            depth: self.depth(),
+            peak_depth: self.counters.peak_depth.load(Ordering::Relaxed),
+            capacity: unsafe {
+                core::ptr::addr_of!((*self.queue.as_ptr()).tx_queue_capacity).read_volatile()
+            },
+        }
+    }
+
+    /// How many items are in the queue right now
+    fn depth(&self) -> u32 {
+        unsafe { core::ptr::addr_of!((*self.queue.as_ptr()).tx_queue_enqueued).read_volatile() }
+    }
+
+    /// How many workers are waiting for an item
+    fn waiting(&self) -> u32 {
+        unsafe {
+            core::ptr::addr_of!((*self.queue.as_ptr()).tx_queue_suspended_count).read_volatile()
+        }
+    }
+}
+
+/// Storage for a work queue, suitable for placing in a `static`.
+///
+/// `WORDS` is the size of the buffer in ThreadX words - use [`words`] to work
+/// it out.
+pub struct StaticWorkQueue<const WORDS: usize> {
+    queue: StaticQueue<WorkItem, WORDS>,
+    counters: Counters,
+}
+
+impl<const WORDS: usize> StaticWorkQueue<WORDS> {
+    /// Reserve space for a work queue. It ends up in `.bss`.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> StaticWorkQueue<WORDS> {
+        StaticWorkQueue {
+            queue: StaticQueue::new(),
+            counters: Counters {
+                submitted: AtomicU32::new(0),
+                completed: AtomicU32::new(0),
+                overruns: AtomicU32::new(0),
+                peak_depth: AtomicU32::new(0),
+            },
+        }
+    }
+
+    /// Get the work queue, if it has been created.
+    pub fn get(&'static self) -> Option<WorkQueue> {
+        self.queue.get().map(|queue| WorkQueue {
+            queue,
+            counters: &self.counters,
+        })
+    }
+
+    /// Create the ThreadX queue behind the work queue.
+    ///
+    /// Returns [`Error::QueueError`] if it has already been created.
+    pub fn create(&'static self, name: &'static CStr) -> Result<WorkQueue, Error> {
+        let queue = self.queue.create(name)?;
+        Ok(WorkQueue {
+            queue,
+            counters: &self.counters,
+        })
+    }
+}
+
+// End of file
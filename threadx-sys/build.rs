@@ -1,16 +1,89 @@
 //! Build Script for threadx-sys
 //!
-//! Calls out to bindgen to generate a Rust crate from the ThreadX header
+//! Generates the ThreadX user configuration header (`tx_user.h`), and then
+//! calls out to bindgen to generate a Rust crate from the ThreadX header
 //! files.
+//!
+//! The layout of the ThreadX control blocks depends on the configuration, so
+//! the applications which compile ThreadX must use the same `tx_user.h` as we
+//! do. We export its location as `DEP_THREADX_INCLUDE` to the build script of
+//! any package which depends on us.
 
 // SPDX-FileCopyrightText: Copyright (c) 2023 Ferrous Systems
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// ThreadX options which are turned on by a Cargo feature of this package, as
+/// (feature, macros) pairs.
+static TX_FEATURE_DEFINES: &[(&str, &[&str])] = &[
+    ("stack-checking", &["TX_ENABLE_STACK_CHECKING"]),
+    (
+        "performance-info",
+        &[
+            "TX_BLOCK_POOL_ENABLE_PERFORMANCE_INFO",
+            "TX_BYTE_POOL_ENABLE_PERFORMANCE_INFO",
+            "TX_EVENT_FLAGS_ENABLE_PERFORMANCE_INFO",
+            "TX_MUTEX_ENABLE_PERFORMANCE_INFO",
+            "TX_QUEUE_ENABLE_PERFORMANCE_INFO",
+            "TX_SEMAPHORE_ENABLE_PERFORMANCE_INFO",
+            "TX_THREAD_ENABLE_PERFORMANCE_INFO",
+            "TX_TIMER_ENABLE_PERFORMANCE_INFO",
+        ],
+    ),
+    (
+        "execution-profile",
+        &["TX_EXECUTION_PROFILE_ENABLE", "TX_EXECUTION_64BIT_TIME"],
+    ),
+    ("fiq-support", &["TX_ENABLE_FIQ_SUPPORT"]),
+];
+
+/// Write the ThreadX configuration header into the given directory.
+fn write_tx_user_h(out_dir: &Path) {
+    let mut config = String::new();
+    config.push_str("/* Generated by threadx-sys/build.rs - do not edit */\n\n");
+    config.push_str("#ifndef TX_USER_H\n#define TX_USER_H\n\n");
+    // Per-thread state for the `threadx` crate: the list of thread-local
+    // values, the innermost scope the thread is running, whether the thread
+    // is panicking and the preemption threshold it had before, and whether
+    // it was spawned
+    config.push_str(
+        "#define TX_THREAD_USER_EXTENSION VOID *tx_thread_rust_tls; VOID *tx_thread_rust_scope; \
+         UINT tx_thread_rust_panicking; UINT tx_thread_rust_threshold; \
+         UINT tx_thread_rust_spawned;\n",
+    );
+    for (feature, defines) in TX_FEATURE_DEFINES {
+        let env_name = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+        if env::var_os(env_name).is_some() {
+            for define in defines.iter() {
+                config.push_str(&format!("#define {}\n", define));
+            }
+            // Tell the build scripts of our dependents, which may need to
+            // compile extra ThreadX source files
+            println!("cargo:{}=1", feature.replace('-', "_"));
+        }
+    }
+    config.push_str("\n#endif\n");
+    std::fs::write(out_dir.join("tx_user.h"), config).expect("Couldn't write tx_user.h");
+}
 
 fn main() {
     let threadx_path = PathBuf::from("../threadx");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    write_tx_user_h(&out_dir);
+    println!("cargo:include={}", out_dir.display());
+
+    // Use the port that matches the target, so the types have the right
+    // layout. Anything that isn't a Cortex-R gets the Cortex-M4 port.
+    let target = env::var("TARGET").unwrap();
+    let (port, cpu_args): (&str, &[&str]) = if target.starts_with("armv7r") {
+        ("cortex_r5", &["-marm", "-mcpu=cortex-r5"])
+    } else {
+        ("cortex_m4", &["-mthumb", "-mcpu=cortex-m4"])
+    };
+
     // The bindgen::Builder is the main entry point
     // to bindgen, and lets you build up options for
     // the resulting bindings.
@@ -22,16 +95,22 @@ fn main() {
         .clang_arg(format!("-I{}", threadx_path.join("common/inc").display()))
         .clang_arg(format!(
             "-I{}",
-            threadx_path.join("ports/cortex_m4/gnu/inc").display()
+            threadx_path
+                .join("ports")
+                .join(port)
+                .join("gnu/inc")
+                .display()
         ))
+        // Use our generated configuration
+        .clang_arg(format!("-I{}", out_dir.display()))
+        .clang_arg("-DTX_INCLUDE_USER_DEFINE_FILE")
         // Some fake local include files
         .clang_arg("-I./include")
         // Disable standard includes (they belong to the host)
         .clang_arg("-nostdinc")
         // Set the target
         .clang_arg("--target=arm")
-        .clang_arg("-mthumb")
-        .clang_arg("-mcpu=cortex-m4")
+        .clang_args(cpu_args)
         // Use softfp
         .clang_arg("-mfloat-abi=soft")
         // We're no_std
@@ -52,7 +131,7 @@ fn main() {
     // Write the bindings to the $OUT_DIR/bindings.rs file.
     let rust_source = bindings.to_string();
 
-    let bindings_out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("bindings.rs");
+    let bindings_out_path = out_dir.join("bindings.rs");
     std::fs::write(bindings_out_path, rust_source).expect("Couldn't write updated bindgen output");
 
     // The user will have to specify the path to the library themselves because
@@ -58,6 +58,99 @@ pub const TX_NO_SUSPENSIONS: UINT = 0;
 pub const TX_NO_MESSAGES: UINT = 0;
 pub const TX_EMPTY: ULONG = 0;
 pub const TX_CLEAR_ID: ULONG = 0;
+pub const TX_STACK_FILL: ULONG = 0xEFEFEFEF;
+/// The ThreadX default, which our `tx_user.h` does not change
+pub const TX_MAX_PRIORITIES: UINT = 32;
 
 /// Operation completed successfully
 pub const TX_SUCCESS: UINT = 0;
+/// Object was deleted
+pub const TX_DELETED: UINT = 0x01;
+/// Invalid memory pool pointer
+pub const TX_POOL_ERROR: UINT = 0x02;
+/// Invalid pointer
+pub const TX_PTR_ERROR: UINT = 0x03;
+/// Wait option used from a non-thread context
+pub const TX_WAIT_ERROR: UINT = 0x04;
+/// Invalid size
+pub const TX_SIZE_ERROR: UINT = 0x05;
+/// Invalid event flags group pointer
+pub const TX_GROUP_ERROR: UINT = 0x06;
+/// Requested event flags not present
+pub const TX_NO_EVENTS: UINT = 0x07;
+/// Invalid option
+pub const TX_OPTION_ERROR: UINT = 0x08;
+/// Invalid queue pointer
+pub const TX_QUEUE_ERROR: UINT = 0x09;
+/// Queue is empty
+pub const TX_QUEUE_EMPTY: UINT = 0x0A;
+/// Queue is full
+pub const TX_QUEUE_FULL: UINT = 0x0B;
+/// Invalid semaphore pointer
+pub const TX_SEMAPHORE_ERROR: UINT = 0x0C;
+/// Semaphore count was zero
+pub const TX_NO_INSTANCE: UINT = 0x0D;
+/// Invalid thread pointer
+pub const TX_THREAD_ERROR: UINT = 0x0E;
+/// Invalid thread priority
+pub const TX_PRIORITY_ERROR: UINT = 0x0F;
+/// Memory could not be allocated
+pub const TX_NO_MEMORY: UINT = 0x10;
+/// Invalid auto-start selection
+pub const TX_START_ERROR: UINT = 0x10;
+/// Object is not in a state where it can be deleted
+pub const TX_DELETE_ERROR: UINT = 0x11;
+/// Thread was not suspended
+pub const TX_RESUME_ERROR: UINT = 0x12;
+/// Invalid caller of this service
+pub const TX_CALLER_ERROR: UINT = 0x13;
+/// Thread is in a state where it cannot be suspended
+pub const TX_SUSPEND_ERROR: UINT = 0x14;
+/// Invalid timer pointer
+pub const TX_TIMER_ERROR: UINT = 0x15;
+/// Invalid tick value
+pub const TX_TICK_ERROR: UINT = 0x16;
+/// Invalid timer activation selection
+pub const TX_ACTIVATE_ERROR: UINT = 0x17;
+/// Invalid preemption threshold
+pub const TX_THRESH_ERROR: UINT = 0x18;
+/// Delayed suspension was lifted
+pub const TX_SUSPEND_LIFTED: UINT = 0x19;
+/// Suspension was aborted by another thread, timer or ISR
+pub const TX_WAIT_ABORTED: UINT = 0x1A;
+/// Thread was not in a suspended state
+pub const TX_WAIT_ABORT_ERROR: UINT = 0x1B;
+/// Invalid mutex pointer
+pub const TX_MUTEX_ERROR: UINT = 0x1C;
+/// Service was unable to get ownership of the object
+pub const TX_NOT_AVAILABLE: UINT = 0x1D;
+/// Object is not owned by the caller
+pub const TX_NOT_OWNED: UINT = 0x1E;
+/// Invalid priority inheritance selection
+pub const TX_INHERIT_ERROR: UINT = 0x1F;
+/// Operation did not complete
+pub const TX_NOT_DONE: UINT = 0x20;
+/// Semaphore ceiling was exceeded
+pub const TX_CEILING_EXCEEDED: UINT = 0x21;
+/// Invalid semaphore ceiling value
+pub const TX_INVALID_CEILING: UINT = 0x22;
+/// Requested feature is not enabled
+pub const TX_FEATURE_NOT_ENABLED: UINT = 0xFF;
+
+// Thread execution states
+
+pub const TX_READY: UINT = 0;
+pub const TX_COMPLETED: UINT = 1;
+pub const TX_TERMINATED: UINT = 2;
+pub const TX_SUSPENDED: UINT = 3;
+pub const TX_SLEEP: UINT = 4;
+pub const TX_QUEUE_SUSP: UINT = 5;
+pub const TX_SEMAPHORE_SUSP: UINT = 6;
+pub const TX_EVENT_FLAG: UINT = 7;
+pub const TX_BLOCK_MEMORY: UINT = 8;
+pub const TX_BYTE_MEMORY: UINT = 9;
+pub const TX_IO_DRIVER: UINT = 10;
+pub const TX_FILE: UINT = 11;
+pub const TX_TCP_IP: UINT = 12;
+pub const TX_MUTEX_SUSP: UINT = 13;
+pub const TX_PRIORITY_CHANGE: UINT = 14;